@@ -0,0 +1,184 @@
+use std::io::Write;
+
+use flate2::write::{ GzEncoder, ZlibEncoder };
+use flate2::Compression;
+
+use crate::http::types::{ HttpRequest, HttpResponse };
+
+// In the order of server preference, the first one the client accepts is used
+const SUPPORTED_ENCODINGS: [&str; 3] = ["br", "gzip", "deflate"];
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+const BROTLI_WINDOW_SIZE: u32 = 22;
+
+fn gzip_compress(bytes: &[u8], level: Compression) -> Result<Vec<u8>,std::io::Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut encoder = GzEncoder::new(&mut buffer, level);
+    encoder.write_all(bytes)?;
+    encoder.finish()?;
+    Ok(buffer)
+}
+
+// The "deflate" content coding is the zlib format (RFC 9110, section 8.4.1.2), not raw deflate
+fn deflate_compress(bytes: &[u8], level: Compression) -> Result<Vec<u8>,std::io::Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut encoder = ZlibEncoder::new(&mut buffer, level);
+    encoder.write_all(bytes)?;
+    encoder.finish()?;
+    Ok(buffer)
+}
+
+/// Brotli-encodes `data` at the default compression level
+pub fn brotli_encode(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    brotli_compress(data, Compression::default().level())
+}
+
+// Brotli qualities go up to 11, the 0-9 compression level is used as is
+fn brotli_compress(bytes: &[u8], quality: u32) -> Result<Vec<u8>, std::io::Error> {
+    let mut buffer: Vec<u8> = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut buffer, BROTLI_BUFFER_SIZE, quality, BROTLI_WINDOW_SIZE);
+        encoder.write_all(bytes)?;
+        encoder.flush()?;
+    }
+    Ok(buffer)
+}
+
+fn encode(encoding: &str, bytes: &[u8], compression_level: u32) -> Result<Vec<u8>, std::io::Error> {
+    match encoding {
+        "br" => brotli_compress(bytes, compression_level),
+        "gzip" => gzip_compress(bytes, Compression::new(compression_level)),
+        _ => deflate_compress(bytes, Compression::new(compression_level))
+    }
+}
+
+// Accept-Encoding: gzip, deflate;q=0.5, br;q=0
+fn accepts_encoding(request: &HttpRequest, encoding: &str) -> bool {
+    request.headers.get_all("Accept-Encoding").iter()
+        .flat_map(|accepted_encodings| accepted_encodings.split(','))
+        .any(|accepted_encoding| {
+            let mut parts = accepted_encoding.split(';').map(|part| part.trim());
+            let name = parts.next().unwrap_or("");
+            let rejected = parts.any(|parameter| {
+                parameter.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()).is_some_and(|q| q == 0.0)
+            });
+            name.eq_ignore_ascii_case(encoding) && !rejected
+        })
+}
+
+fn is_compressible(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or("").trim();
+    media_type.starts_with("text/")
+        || media_type == "application/json"
+        || media_type == "application/javascript"
+        || media_type == "image/svg+xml"
+}
+
+// Cache-Control: no-cache, no-transform
+fn forbids_transform(cache_control_values: &[String]) -> bool {
+    cache_control_values.iter()
+        .flat_map(|cache_control| cache_control.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"))
+}
+
+// The encoded bytes differ from the identity representation, so its strong validator no longer applies.
+// The weak one still matches If-None-Match, which uses the weak comparison.
+fn weaken_etag(response: &mut HttpResponse) {
+    let strong_etag = response.headers.get("ETag").filter(|etag| !etag.starts_with("W/")).map(String::from);
+    if let Some(etag) = strong_etag {
+        response.headers.set("ETag", format!("W/{}", etag));
+    }
+}
+
+// Whether a response with this content type varies by Accept-Encoding
+fn is_transformable(request: &HttpRequest, response: &HttpResponse, content_type: Option<&str>) -> bool {
+    !response.headers.contains("Content-Encoding")
+        && !forbids_transform(request.headers.get_all("Cache-Control"))
+        && !forbids_transform(response.headers.get_all("Cache-Control"))
+        && content_type.is_some_and(is_compressible)
+}
+
+// Compressed streams are sent chunked, which HTTP/1.0 clients do not understand
+fn choose_encoding(request: &HttpRequest, streamed: bool) -> Option<&'static str> {
+    if streamed {
+        Some("gzip").filter(|encoding| accepts_encoding(request, encoding) && request.http_version != "HTTP/1.0")
+    } else {
+        SUPPORTED_ENCODINGS.into_iter().find(|encoding| accepts_encoding(request, encoding))
+    }
+}
+
+/// A 304 stands in for the 200 response to the same request, so it gets the same Vary header and,
+/// if that response would be encoded, the same weak ETag
+pub fn describe_not_modified(request: &HttpRequest, response: &mut HttpResponse, content_type: &str, streamed: bool) {
+    if response.status != 304 || !is_transformable(request, response, Some(content_type)) {
+        return;
+    }
+    response.headers.append(String::from("Vary"), String::from("Accept-Encoding"));
+    if choose_encoding(request, streamed).is_some() {
+        weaken_etag(response);
+    }
+}
+
+// Applied to every response, so that handlers only produce the identity encoding
+pub fn compress_response(request: &HttpRequest, response: &mut HttpResponse, compression_level: u32) -> Result<(), std::io::Error> {
+    let compressible = response.status == 200
+        && (!response.body.is_empty() || response.streamed_body.is_some())
+        && is_transformable(request, response, response.headers.get("Content-Type"));
+    if !compressible {
+        return Ok(());
+    }
+    response.headers.append(String::from("Vary"), String::from("Accept-Encoding"));
+    let encoding = choose_encoding(request, response.streamed_body.is_some());
+    if let Some(streamed_body) = &mut response.streamed_body {
+        if let Some(encoding) = encoding {
+            streamed_body.gzip_level = Some(compression_level);
+            response.headers.append(String::from("Content-Encoding"), String::from(encoding));
+            response.headers.remove("Content-Length");
+            response.headers.set("Transfer-Encoding", String::from("chunked"));
+            weaken_etag(response);
+        }
+    } else if let Some(encoding) = encoding {
+        response.body = encode(encoding, &response.body, compression_level)?.into();
+        response.headers.append(String::from("Content-Encoding"), String::from(encoding));
+        response.headers.set("Content-Length", response.body.len().to_string());
+        weaken_etag(response);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn sample_text() -> Vec<u8> {
+        (0..2000).map(|line| format!("line {} of a fairly repetitive text, {}\n", line, line % 7)).collect::<String>().into_bytes()
+    }
+
+    #[test]
+    fn higher_gzip_level_compresses_at_least_as_well() {
+        let fastest = gzip_compress(&sample_text(), Compression::new(1)).unwrap();
+        let best = gzip_compress(&sample_text(), Compression::new(9)).unwrap();
+        assert!(best.len() <= fastest.len(), "level 9: {} bytes, level 1: {} bytes", best.len(), fastest.len());
+        assert!(fastest.len() < sample_text().len());
+    }
+
+    #[test]
+    fn brotli_round_trips() {
+        let encoded = brotli_encode(&sample_text()).unwrap();
+        let mut decoded: Vec<u8> = Vec::new();
+        brotli::Decompressor::new(encoded.as_slice(), BROTLI_BUFFER_SIZE).read_to_end(&mut decoded).unwrap();
+        assert!(encoded.len() < sample_text().len());
+        assert!(decoded == sample_text(), "the decompressed text differs from the original");
+    }
+
+    #[test]
+    fn no_transform_is_found_among_other_directives() {
+        let directives = |values: &[&str]| values.iter().map(|value| String::from(*value)).collect::<Vec<String>>();
+        assert!(forbids_transform(&directives(&["no-cache, No-Transform"])));
+        assert!(forbids_transform(&directives(&["max-age=60", "no-transform"])));
+        assert!(!forbids_transform(&directives(&["no-cache, max-age=60"])));
+        assert!(!forbids_transform(&[]));
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flate2::Compression;
+
+use crate::file_cache::FileCache;
+use crate::handlers::files::content_type_for;
+
+#[derive(Clone)]
+pub struct ErrorPage {
+    pub content_type: &'static str,
+    pub body: Vec<u8>
+}
+
+impl fmt::Debug for ErrorPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorPage")
+            .field("content_type", &self.content_type)
+            .field("length", &self.body.len())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfiguration {
+    pub directory: Option<String>,
+    pub compression_level: u32,
+    pub file_cache: Option<Arc<FileCache>>,
+    pub index: bool,
+    pub autoindex: bool,
+    pub error_pages: HashMap<u16, ErrorPage>,
+    pub cors_allow_origin: Option<String>,
+    pub cors_allow_methods: String,
+    pub cors_allow_headers: String,
+    pub normalize_paths: bool,
+    pub basic_auth: Option<BasicAuth>,
+    pub max_total_requests: Option<u64>,
+    pub max_request_memory: Option<usize>,
+    pub allowed_referers: Vec<String>,
+    pub allow_missing_referer: bool,
+    pub preload: Vec<String>,
+    pub slow_request_threshold: Option<Duration>,
+    pub enable_debug_routes: bool
+}
+
+impl Default for ServerConfiguration {
+    fn default() -> ServerConfiguration {
+        ServerConfiguration {
+            directory: None,
+            compression_level: Compression::default().level(),
+            file_cache: None,
+            index: true,
+            autoindex: false,
+            error_pages: HashMap::new(),
+            cors_allow_origin: None,
+            cors_allow_methods: String::from("GET, HEAD, POST, PUT, OPTIONS"),
+            cors_allow_headers: String::from("Content-Type"),
+            normalize_paths: false,
+            basic_auth: None,
+            max_total_requests: None,
+            max_request_memory: None,
+            allowed_referers: Vec::new(),
+            allow_missing_referer: true,
+            preload: Vec::new(),
+            slow_request_threshold: None,
+            enable_debug_routes: false
+        }
+    }
+}
+
+fn parse_compression_level(value: Option<&String>) -> Result<u32, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --compression-level"))?;
+    match value.parse::<u32>() {
+        Ok(level) if level <= 9 => Ok(level),
+        _ => Err(Error::other(format!("Invalid compression level '{}', expected a number from 0 to 9", value)))
+    }
+}
+
+fn parse_file_cache_size(value: Option<&String>) -> Result<usize, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --file-cache-size"))?;
+    value.parse::<usize>().map_err(|_| Error::other(format!("Invalid file cache size '{}', expected a number of bytes", value)))
+}
+
+// --error-page 404=/path/to/404.html
+fn parse_error_page(value: Option<&String>) -> Result<(u16, ErrorPage), std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --error-page"))?;
+    let (status, path) = value.split_once('=')
+        .ok_or(Error::other(format!("Invalid error page '{}', expected STATUS=PATH", value)))?;
+    let status = status.parse::<u16>().ok().filter(|status| (400..600).contains(status))
+        .ok_or(Error::other(format!("Invalid error page status '{}', expected a status from 400 to 599", status)))?;
+    let path = Path::new(path);
+    let content_type = if content_type_for(path) == "text/html" { "text/html" } else { "text/plain" };
+    let body = fs::read(path)
+        .map_err(|e| Error::other(format!("Cannot read error page '{}': {}", path.display(), e)))?;
+    Ok((status, ErrorPage { content_type, body }))
+}
+
+// --basic-auth user:password
+fn parse_basic_auth(value: Option<&String>) -> Result<BasicAuth, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --basic-auth"))?;
+    match value.split_once(':') {
+        Some((username, password)) if !username.is_empty() =>
+            Ok(BasicAuth { username: String::from(username), password: String::from(password) }),
+        _ => Err(Error::other("Invalid basic auth credentials, expected USER:PASSWORD"))
+    }
+}
+
+fn parse_max_total_requests(value: Option<&String>) -> Result<u64, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --max-total-requests"))?;
+    match value.parse::<u64>() {
+        Ok(max_total_requests) if max_total_requests > 0 => Ok(max_total_requests),
+        _ => Err(Error::other(format!("Invalid maximum total requests '{}', expected a positive number", value)))
+    }
+}
+
+fn parse_max_request_memory(value: Option<&String>) -> Result<usize, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --max-request-memory"))?;
+    match value.parse::<usize>() {
+        Ok(max_bytes) if max_bytes > 0 => Ok(max_bytes),
+        _ => Err(Error::other(format!("Invalid maximum request memory '{}', expected a positive number of bytes", value)))
+    }
+}
+
+// --allowed-referers example.com,cdn.example.com:8080
+fn parse_allowed_referers(value: Option<&String>) -> Result<Vec<String>, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --allowed-referers"))?;
+    Ok(value.split(',').map(|host| host.trim()).filter(|host| !host.is_empty()).map(String::from).collect())
+}
+
+fn parse_slow_request_threshold(value: Option<&String>) -> Result<Duration, std::io::Error> {
+    let value = value.ok_or(Error::other("Missing value for --slow-request-threshold"))?;
+    value.parse::<u64>().map(Duration::from_millis)
+        .map_err(|_| Error::other(format!("Invalid slow request threshold '{}', expected a number of milliseconds", value)))
+}
+
+impl ServerConfiguration {
+    pub fn from_args(args: &[String]) -> Result<ServerConfiguration, std::io::Error> {
+        let mut configuration = ServerConfiguration::default();
+        for (idx, arg) in args.iter().enumerate() {
+            match arg.as_str() {
+                "-d" | "--directory" => configuration.directory = args.get(idx + 1).map(String::from),
+                "--compression-level" => configuration.compression_level = parse_compression_level(args.get(idx + 1))?,
+                "--file-cache-size" => {
+                    let max_bytes = parse_file_cache_size(args.get(idx + 1))?;
+                    configuration.file_cache = if max_bytes > 0 { Some(Arc::new(FileCache::new(max_bytes))) } else { None };
+                }
+                "--index" => configuration.index = true,
+                "--no-index" => configuration.index = false,
+                "--autoindex" => configuration.autoindex = true,
+                "--no-autoindex" => configuration.autoindex = false,
+                "--error-page" => {
+                    let (status, error_page) = parse_error_page(args.get(idx + 1))?;
+                    configuration.error_pages.insert(status, error_page);
+                }
+                "--max-total-requests" => configuration.max_total_requests = Some(parse_max_total_requests(args.get(idx + 1))?),
+                "--max-request-memory" => configuration.max_request_memory = Some(parse_max_request_memory(args.get(idx + 1))?),
+                "--allowed-referers" => configuration.allowed_referers = parse_allowed_referers(args.get(idx + 1))?,
+                "--allow-missing-referer" => configuration.allow_missing_referer = true,
+                "--deny-missing-referer" => configuration.allow_missing_referer = false,
+                "--preload" => {
+                    let pattern = args.get(idx + 1).ok_or(Error::other("Missing value for --preload"))?;
+                    configuration.preload.push(String::from(pattern));
+                }
+                "--slow-request-threshold" => {
+                    configuration.slow_request_threshold = Some(parse_slow_request_threshold(args.get(idx + 1))?);
+                }
+                "--enable-debug-routes" => configuration.enable_debug_routes = true,
+                "--basic-auth" => configuration.basic_auth = Some(parse_basic_auth(args.get(idx + 1))?),
+                "--normalize-paths" => configuration.normalize_paths = true,
+                "--no-normalize-paths" => configuration.normalize_paths = false,
+                "--cors-allow-origin" => configuration.cors_allow_origin = args.get(idx + 1).map(String::from),
+                "--cors-allow-methods" => if let Some(methods) = args.get(idx + 1) {
+                    configuration.cors_allow_methods = String::from(methods);
+                },
+                "--cors-allow-headers" => if let Some(headers) = args.get(idx + 1) {
+                    configuration.cors_allow_headers = String::from(headers);
+                },
+                _ => {},
+            }
+        }
+        Ok(configuration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        std::iter::once("http-server").chain(args.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn compression_level_is_parsed() {
+        let configuration = ServerConfiguration::from_args(&args(&["--compression-level", "9"])).unwrap();
+        assert_eq!(configuration.compression_level, 9);
+        let configuration = ServerConfiguration::from_args(&args(&[])).unwrap();
+        assert_eq!(configuration.compression_level, Compression::default().level());
+    }
+
+    #[test]
+    fn invalid_compression_level_is_rejected() {
+        for level in ["10", "-1", "fast"] {
+            assert!(ServerConfiguration::from_args(&args(&["--compression-level", level])).is_err(), "{} was accepted", level);
+        }
+        assert!(ServerConfiguration::from_args(&args(&["--compression-level"])).is_err());
+    }
+}
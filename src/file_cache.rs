@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::time::SystemTime;
+
+use crate::handlers::files::STREAMING_THRESHOLD_BYTES;
+
+struct CachedFile {
+    modified: Option<SystemTime>,
+    contents: Arc<[u8]>,
+    last_used: u64
+}
+
+struct CacheState {
+    entries: HashMap<PathBuf, CachedFile>,
+    total_bytes: usize,
+    clock: u64
+}
+
+/// Keeps the contents of recently served files in memory, bounded by the total number of cached bytes.
+/// An entry is only reused while the file's modification time and size are unchanged. The contents are
+/// shared with the responses that serve them rather than copied for every request.
+pub struct FileCache {
+    max_bytes: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64
+}
+
+impl FileCache {
+    pub fn new(max_bytes: usize) -> FileCache {
+        FileCache {
+            max_bytes,
+            state: Mutex::new(CacheState { entries: HashMap::new(), total_bytes: 0, clock: 0 }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0)
+        }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn read(&self, path: &Path, metadata: &fs::Metadata) -> Result<Arc<[u8]>, std::io::Error> {
+        let modified = metadata.modified().ok();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.clock += 1;
+            let now = state.clock;
+            if let Some(entry) = state.entries.get_mut(path) {
+                if entry.modified.is_some() && entry.modified == modified && entry.contents.len() as u64 == metadata.len() {
+                    entry.last_used = now;
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Arc::clone(&entry.contents));
+                }
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let contents: Arc<[u8]> = fs::read(path)?.into();
+        self.insert(path, modified, Arc::clone(&contents));
+        Ok(contents)
+    }
+
+    /// Reads every file under `directory` matching one of the glob `patterns`, relative to `directory`.
+    /// `*` and `?` match within a path segment and `**` any number of segments. Files that do not fit
+    /// into the remaining cache space, or that are large enough to be streamed, are skipped and nothing
+    /// is evicted, as are entries that cannot be read. Returns the number of cached files.
+    pub fn preload(&self, directory: &Path, patterns: &[String]) -> usize {
+        let patterns: Vec<Vec<&str>> = patterns.iter()
+            .map(|pattern| pattern.split('/').filter(|segment| !segment.is_empty()).collect())
+            .collect();
+        let mut preloaded = 0;
+        let mut pending_directories: Vec<(PathBuf, Vec<String>)> = vec![(directory.to_path_buf(), Vec::new())];
+        // Preloading is only an optimization, an entry that cannot be read is skipped rather than failing startup
+        let skip = |path: &Path, error: std::io::Error| println!("Skipping '{}' while preloading: {}", path.display(), error);
+        while let Some((current_directory, relative_segments)) = pending_directories.pop() {
+            let entries = match fs::read_dir(&current_directory) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    skip(&current_directory, e);
+                    continue;
+                }
+            };
+            for entry in entries {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        skip(&current_directory, e);
+                        continue;
+                    }
+                };
+                let mut segments = relative_segments.clone();
+                segments.push(entry.file_name().to_string_lossy().into_owned());
+                let file_type = match entry.file_type() {
+                    Ok(file_type) => file_type,
+                    Err(e) => {
+                        skip(&entry.path(), e);
+                        continue;
+                    }
+                };
+                if file_type.is_dir() {
+                    pending_directories.push((entry.path(), segments));
+                } else if file_type.is_file() {
+                    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+                    if !patterns.iter().any(|pattern| glob_matches(pattern, &segments)) {
+                        continue;
+                    }
+                    match self.preload_file(&entry.path()) {
+                        Ok(true) => preloaded += 1,
+                        Ok(false) => {}
+                        Err(e) => skip(&entry.path(), e)
+                    }
+                }
+            }
+        }
+        preloaded
+    }
+
+    // Files that are streamed are never read through the cache, so preloading them would only waste space
+    fn preload_file(&self, path: &Path) -> Result<bool, std::io::Error> {
+        let metadata = fs::metadata(path)?;
+        if metadata.len() >= STREAMING_THRESHOLD_BYTES {
+            return Ok(false);
+        }
+        let fits = {
+            let state = self.state.lock().unwrap();
+            state.total_bytes as u64 + metadata.len() <= self.max_bytes as u64
+        };
+        if !fits {
+            return Ok(false);
+        }
+        self.insert(path, metadata.modified().ok(), fs::read(path)?.into());
+        Ok(self.state.lock().unwrap().entries.contains_key(path))
+    }
+
+    fn insert(&self, path: &Path, modified: Option<SystemTime>, contents: Arc<[u8]>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(stale) = state.entries.remove(path) {
+            state.total_bytes -= stale.contents.len();
+        }
+        if modified.is_none() || contents.len() > self.max_bytes {
+            return;
+        }
+        while state.total_bytes + contents.len() > self.max_bytes {
+            let least_recently_used = state.entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone());
+            match least_recently_used.and_then(|path| state.entries.remove(&path)) {
+                Some(evicted) => state.total_bytes -= evicted.contents.len(),
+                None => break
+            }
+        }
+        state.clock += 1;
+        let last_used = state.clock;
+        state.total_bytes += contents.len();
+        state.entries.insert(path.to_path_buf(), CachedFile { modified, contents, last_used });
+    }
+}
+
+fn segment_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => segment_matches(&pattern[1..], name) || (!name.is_empty() && segment_matches(pattern, &name[1..])),
+        (Some(b'?'), Some(_)) => segment_matches(&pattern[1..], &name[1..]),
+        (Some(expected), Some(actual)) if expected == actual => segment_matches(&pattern[1..], &name[1..]),
+        _ => false
+    }
+}
+
+fn glob_matches(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => glob_matches(&pattern[1..], path) || (!path.is_empty() && glob_matches(pattern, &path[1..])),
+        (Some(segment_pattern), Some(segment)) =>
+            segment_matches(segment_pattern.as_bytes(), segment.as_bytes()) && glob_matches(&pattern[1..], &path[1..]),
+        _ => false
+    }
+}
+
+impl fmt::Debug for FileCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileCache")
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn scratch_file(name: &str, contents: &[u8]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("file-cache-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn read(file_cache: &FileCache, path: &Path) -> Vec<u8> {
+        file_cache.read(path, &fs::metadata(path).unwrap()).unwrap().to_vec()
+    }
+
+    #[test]
+    fn unchanged_file_is_served_from_the_cache() {
+        let path = scratch_file("unchanged.txt", b"cached");
+        let file_cache = FileCache::new(1024);
+
+        assert_eq!(read(&file_cache, &path), b"cached");
+        assert_eq!(read(&file_cache, &path), b"cached");
+
+        assert_eq!((file_cache.hits(), file_cache.misses()), (1, 1));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn modified_file_is_read_again() {
+        let path = scratch_file("modified.txt", b"before");
+        let file_cache = FileCache::new(1024);
+        assert_eq!(read(&file_cache, &path), b"before");
+
+        // Same size, only the modification time tells the versions apart
+        fs::write(&path, b"after!").unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap() + Duration::from_secs(1);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+
+        assert_eq!(read(&file_cache, &path), b"after!");
+        assert_eq!((file_cache.hits(), file_cache.misses()), (0, 2));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let first = scratch_file("first.txt", &[b'1'; 8]);
+        let second = scratch_file("second.txt", &[b'2'; 8]);
+        let file_cache = FileCache::new(10);
+
+        read(&file_cache, &first);
+        read(&file_cache, &second);
+        read(&file_cache, &first);
+
+        assert_eq!((file_cache.hits(), file_cache.misses()), (0, 3));
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn hits_share_the_cached_contents() {
+        let path = scratch_file("shared.txt", b"shared");
+        let file_cache = FileCache::new(1024);
+        let metadata = fs::metadata(&path).unwrap();
+
+        let first = file_cache.read(&path, &metadata).unwrap();
+        let second = file_cache.read(&path, &metadata).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second), "the cached contents were copied");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use crate::http::types::{ HttpRequest, HttpResponse };
+use crate::http::uri::parse_query;
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// [["name","value"],...] keeps both the order and repeated names
+fn json_pairs<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    let pairs: Vec<String> = pairs.map(|(name, value)| format!("[{},{}]", json_string(name), json_string(value))).collect();
+    format!("[{}]", pairs.join(","))
+}
+
+fn is_token(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+// Headers that frame the message or describe the connection are owned by the server, not the client
+const RESERVED_HEADERS: [&str; 8] = [
+    "Content-Length", "Transfer-Encoding", "Connection", "Keep-Alive",
+    "Proxy-Connection", "TE", "Trailer", "Upgrade"
+];
+
+fn is_reserved_header(name: &str) -> bool {
+    RESERVED_HEADERS.iter().any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
+// ?header=X-Test:1, a CR or LF in a header would let the client split the response
+fn parse_response_header(header: &str) -> Option<(&str, &str)> {
+    let (name, value) = header.split_once(':')?;
+    let value = value.trim();
+    let is_valid = is_token(name) && !is_reserved_header(name) && !value.chars().any(|c| c.is_control());
+    if is_valid { Some((name, value)) } else { None }
+}
+
+// Statuses that cannot carry the reflected body are not accepted
+fn parse_status(status: &str) -> Option<u16> {
+    status.parse::<u16>().ok().filter(|status| (200..600).contains(status) && *status != 204 && *status != 304)
+}
+
+/// GET /mirror?status=201&header=X-Test:1 reflects the request as JSON, with the status and headers asked for
+pub fn handle_mirror(request: &HttpRequest) -> Result<HttpResponse, std::io::Error> {
+    let Some(query) = parse_query(&request.uri) else {
+        return Ok(HttpResponse::builder(400).text("Malformed query string").build());
+    };
+    let mut status = 200;
+    let mut response_headers: Vec<(&str, &str)> = Vec::new();
+    for (name, value) in query.iter() {
+        match name.as_str() {
+            "status" => match parse_status(value) {
+                Some(parsed_status) => status = parsed_status,
+                None => return Ok(HttpResponse::builder(400).text(&format!("Invalid status '{}'", value)).build())
+            },
+            "header" => match parse_response_header(value) {
+                Some(header) => response_headers.push(header),
+                None => return Ok(HttpResponse::builder(400).text("Invalid header, expected NAME:VALUE without control characters and not a framing or hop-by-hop header").build())
+            },
+            _ => {}
+        }
+    }
+    let path = request.target.path().unwrap_or("");
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let body = format!("{{\"method\":{},\"path\":{},\"query\":{},\"headers\":{}}}",
+        json_string(request.method.as_str()),
+        json_string(path),
+        json_pairs(query.iter().map(|(name, value)| (name.as_str(), value.as_str()))),
+        json_pairs(request.headers.iter()));
+    let mut response = HttpResponse::builder(status);
+    for (name, value) in response_headers {
+        response = response.header(name, value);
+    }
+    Ok(response.json(&body).build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_headers_are_parsed() {
+        assert_eq!(parse_response_header("X-Test: 1"), Some(("X-Test", "1")));
+        assert_eq!(parse_response_header("Cache-Control:no-store"), Some(("Cache-Control", "no-store")));
+    }
+
+    #[test]
+    fn control_characters_and_invalid_names_are_rejected() {
+        assert_eq!(parse_response_header("X-Test:1\r\nSet-Cookie: a=b"), None);
+        assert_eq!(parse_response_header("X Test:1"), None);
+        assert_eq!(parse_response_header("X-Test"), None);
+    }
+
+    #[test]
+    fn framing_and_hop_by_hop_headers_are_rejected() {
+        for header in ["Content-Length:0", "transfer-encoding:chunked", "Connection:keep-alive", "Upgrade:websocket"] {
+            assert_eq!(parse_response_header(header), None, "{} was accepted", header);
+        }
+    }
+
+    #[test]
+    fn statuses_without_body_are_rejected() {
+        assert_eq!(parse_status("201"), Some(201));
+        assert_eq!(parse_status("204"), None);
+        assert_eq!(parse_status("304"), None);
+        assert_eq!(parse_status("100"), None);
+        assert_eq!(parse_status("600"), None);
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+}
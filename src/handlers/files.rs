@@ -0,0 +1,373 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{ ErrorKind, Write };
+use std::path::{ Path, PathBuf };
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use crate::compression::describe_not_modified;
+use crate::config::ServerConfiguration;
+use crate::file_cache::FileCache;
+use crate::http::date::{ format_http_date, parse_http_date, truncate_to_seconds };
+use crate::http::types::{ Body, HttpMethod, HttpRequest, HttpResponse };
+use crate::http::uri::{ percent_decode, percent_encode_segment };
+
+// EROFS, ErrorKind::ReadOnlyFilesystem is only available from Rust 1.83 on
+#[cfg(unix)]
+const READ_ONLY_FILESYSTEM_ERROR: i32 = 30;
+
+// Larger files are streamed from disk rather than read into memory, bypassing the file cache
+pub(crate) const STREAMING_THRESHOLD_BYTES: u64 = 1024 * 1024;
+
+// Derived from the size and modification time, so it changes whenever the file is rewritten
+fn file_etag(metadata: &fs::Metadata) -> String {
+    let modified = metadata.modified().ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    format!("\"{:x}-{:x}.{:x}\"", metadata.len(), modified.as_secs(), modified.subsec_nanos())
+}
+
+// If-None-Match uses the weak comparison, see RFC 7232, section 3.2
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque_tag = |tag: &str| String::from(tag.trim().trim_start_matches("W/"));
+    if_none_match.split(',').any(|tag| tag.trim() == "*" || opaque_tag(tag) == opaque_tag(etag))
+}
+
+fn is_not_modified(request: &HttpRequest, etag: &str, last_modified: Option<SystemTime>) -> bool {
+    // If-None-Match takes precedence over If-Modified-Since, RFC 7232, section 6
+    if let Some(if_none_match) = request.headers.get("If-None-Match") {
+        return etag_matches(if_none_match, etag);
+    }
+    match (request.headers.get("If-Modified-Since").and_then(parse_http_date), last_modified) {
+        (Some(if_modified_since), Some(last_modified)) => truncate_to_seconds(last_modified) <= if_modified_since,
+        _ => false
+    }
+}
+
+fn read_file(file_path: &Path, metadata: &fs::Metadata, file_cache: Option<&FileCache>) -> Result<Body, std::io::Error> {
+    match file_cache {
+        Some(file_cache) => file_cache.read(file_path, metadata).map(Body::from),
+        None => fs::read(file_path).map(Body::from)
+    }
+}
+
+fn serve_file(request: &HttpRequest, file_path: &Path, content_type: &str, file_cache: Option<&FileCache>) -> Result<HttpResponse, std::io::Error> {
+    let metadata = fs::metadata(file_path)?;
+    let etag = file_etag(&metadata);
+    let last_modified = metadata.modified().ok();
+    if is_not_modified(request, &etag, last_modified) {
+        let mut response = HttpResponse::not_modified(&etag);
+        if let Some(last_modified) = last_modified {
+            response.headers.append(String::from("Last-Modified"), format_http_date(last_modified));
+        }
+        describe_not_modified(request, &mut response, content_type, metadata.len() >= STREAMING_THRESHOLD_BYTES);
+        return Ok(response);
+    }
+    let mut response = HttpResponse::builder(200)
+        .header("Content-Type", content_type)
+        .header("ETag", &etag);
+    if let Some(last_modified) = last_modified {
+        response = response.header("Last-Modified", &format_http_date(last_modified));
+    }
+    if metadata.len() >= STREAMING_THRESHOLD_BYTES {
+        let file = fs::File::open(file_path)?;
+        return Ok(response.stream(Box::new(file), metadata.len()).build());
+    }
+    let file_bytes = read_file(file_path, &metadata, file_cache)?;
+    Ok(response.body(file_bytes).build())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// The decoded segments of the request path as the directory was resolved from them, empty and `.` segments
+// are skipped there, so `//docs/` must not turn into a protocol-relative link to the host `docs`
+fn listed_path_segments(request: &HttpRequest) -> Vec<String> {
+    let request_path = request.target.path().unwrap_or("/");
+    let request_path = request_path.split_once('?').map_or(request_path, |(path, _)| path);
+    request_path.split('/')
+        .filter_map(percent_decode)
+        .filter(|segment| !segment.is_empty() && segment != ".")
+        .collect()
+}
+
+fn list_directory(request: &HttpRequest, directory_path: &Path) -> Result<HttpResponse, std::io::Error> {
+    let segments = listed_path_segments(request);
+    let display_path: String = segments.iter().map(|segment| format!("/{}", segment)).collect();
+    let base_href: String = segments.iter().map(|segment| format!("/{}", percent_encode_segment(segment))).collect();
+    let mut entry_names: Vec<(String, &str)> = Vec::new();
+    for entry in fs::read_dir(directory_path)? {
+        let entry = entry?;
+        let suffix = if entry.file_type()?.is_dir() { "/" } else { "" };
+        entry_names.push((entry.file_name().to_string_lossy().into_owned(), suffix));
+    }
+    entry_names.sort();
+    let mut listing = format!("<!DOCTYPE html>\n<html>\n<head><title>Index of {0}/</title></head>\n<body>\n<h1>Index of {0}/</h1>\n<ul>\n", escape_html(&display_path));
+    for (entry_name, suffix) in entry_names {
+        let href = format!("{}/{}{}", base_href, percent_encode_segment(&entry_name), suffix);
+        listing.push_str(&format!("<li><a href=\"{}\">{}{}</a></li>\n", escape_html(&href), escape_html(&entry_name), suffix));
+    }
+    listing.push_str("</ul>\n</body>\n</html>\n");
+    Ok(HttpResponse::builder(200)
+        .header("Content-Type", "text/html")
+        .body(listing.into_bytes())
+        .build())
+}
+
+// An index file takes precedence, the generated listing is only a fallback when enabled
+fn handle_get_directory(request: &HttpRequest, directory_path: &Path, server_configuration: &ServerConfiguration) -> Result<HttpResponse, std::io::Error> {
+    let index_path = directory_path.join("index.html");
+    if server_configuration.index && index_path.is_file() {
+        serve_file(request, &index_path, "text/html", server_configuration.file_cache.as_deref())
+    } else if server_configuration.autoindex {
+        list_directory(request, directory_path)
+    } else {
+        Ok(HttpResponse::not_found())
+    }
+}
+
+// Referer: https://example.com:8080/page.html, Origin: https://example.com:8080
+fn referring_authority(url: &str) -> Option<&str> {
+    let (_, without_scheme) = url.split_once("://")?;
+    let authority_end = without_scheme.find(['/', '?', '#']).unwrap_or(without_scheme.len());
+    let authority = &without_scheme[..authority_end];
+    // Credentials in a referring URL are never meaningful for the comparison
+    Some(authority.rsplit_once('@').map_or(authority, |(_, host)| host))
+}
+
+// Images are only served to pages hosted on one of the allowed referers, an empty list disables the check
+fn is_hotlink(request: &HttpRequest, file_path: &Path, server_configuration: &ServerConfiguration) -> bool {
+    if server_configuration.allowed_referers.is_empty() || !content_type_for(file_path).starts_with("image/") {
+        return false;
+    }
+    let referer = request.headers.get("Referer").or(request.headers.get("Origin"));
+    match referer {
+        Some(referer) => {
+            let is_allowed = referring_authority(referer).is_some_and(|authority| {
+                let host = authority.rsplit_once(':').map_or(authority, |(host, _)| host);
+                server_configuration.allowed_referers.iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(authority) || allowed.eq_ignore_ascii_case(host))
+            });
+            !is_allowed
+        }
+        None => !server_configuration.allow_missing_referer
+    }
+}
+
+fn handle_get_file(request: &HttpRequest, file_path: &Path, server_configuration: &ServerConfiguration) -> Result<HttpResponse, std::io::Error> {
+    if file_path.is_dir() {
+        handle_get_directory(request, file_path, server_configuration)
+    } else if is_hotlink(request, file_path, server_configuration) {
+        Ok(HttpResponse::forbidden())
+    } else if file_path.exists() {
+        serve_file(request, file_path, "application/octet-stream", server_configuration.file_cache.as_deref())
+    } else {
+        Ok(HttpResponse::not_found())
+    }
+}
+
+// The path is already resolved inside the served directory, so intermediate directories stay within it too
+fn create_parent_directories(file_path: &Path) -> Result<(), std::io::Error> {
+    match file_path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(())
+    }
+}
+
+fn handle_post_file(request: &HttpRequest, file_path: &Path) -> Result<HttpResponse, std::io::Error> {
+    create_parent_directories(file_path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(file_path)?;
+    println!("Length of request body = {}", request.body.len());
+    file.write_all(&request.body)?;
+    Ok(HttpResponse::builder(201).text("Uploaded successfully").build())
+}
+
+// Unlike POST, PUT replaces the resource as a whole, 201 tells the client it was created and 204 that it was replaced
+fn handle_put_file(request: &HttpRequest, file_path: &Path) -> Result<HttpResponse, std::io::Error> {
+    // A directory cannot be replaced by a file
+    if file_path.is_dir() {
+        return Ok(HttpResponse::builder(409).text("A directory exists at this path").build());
+    }
+    let existed = file_path.is_file();
+    create_parent_directories(file_path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(file_path)?;
+    file.write_all(&request.body)?;
+    if existed {
+        Ok(HttpResponse::builder(204).build())
+    } else {
+        Ok(HttpResponse::builder(201).text("Created").build())
+    }
+}
+
+enum PathRejection {
+    // Invalid percent-encoding or characters that can never be part of a file name
+    Malformed,
+    // Outside of the served directory, or no directory is served at all
+    Unavailable
+}
+
+impl PathRejection {
+    fn to_response(&self) -> HttpResponse {
+        match self {
+            PathRejection::Malformed => HttpResponse::bad_request(),
+            PathRejection::Unavailable => HttpResponse::not_found()
+        }
+    }
+}
+
+// Maps a request path onto the served directory, refusing anything that could step outside of it.
+// Segments are checked after percent-decoding, so that %2e%2e or %00 cannot slip through.
+fn resolve_file_path(directory: &str, relative_path: &str) -> Result<PathBuf, PathRejection> {
+    let relative_path = relative_path.split_once('?').map_or(relative_path, |(path, _)| path);
+    let mut file_path = PathBuf::from(directory);
+    for segment in relative_path.split('/') {
+        let segment = percent_decode(segment).ok_or(PathRejection::Malformed)?;
+        if segment.chars().any(|c| c.is_control() || c == '/' || c == '\\') {
+            return Err(PathRejection::Malformed);
+        }
+        match segment.as_str() {
+            "" => continue,
+            ".." => return Err(PathRejection::Unavailable),
+            segment => file_path.push(segment)
+        }
+    }
+    Ok(file_path)
+}
+
+fn resolve_in_directory(relative_path: &str, server_configuration: &ServerConfiguration) -> Result<PathBuf, PathRejection> {
+    match &server_configuration.directory {
+        Some(directory) => resolve_file_path(directory, relative_path),
+        None => Err(PathRejection::Unavailable)
+    }
+}
+
+pub fn content_type_for(file_path: &Path) -> &'static str {
+    let extension = file_path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream"
+    }
+}
+
+#[cfg(unix)]
+fn is_read_only_filesystem(error: &std::io::Error) -> bool {
+    error.raw_os_error() == Some(READ_ONLY_FILESYSTEM_ERROR)
+}
+
+#[cfg(not(unix))]
+fn is_read_only_filesystem(_error: &std::io::Error) -> bool {
+    false
+}
+
+// A directory that is not writable, or lives on a read-only mount, rejects uploads with 403 rather than 500
+fn file_error_response(error: &std::io::Error) -> HttpResponse {
+    match error.kind() {
+        ErrorKind::NotFound => HttpResponse::not_found(),
+        _ if error.kind() == ErrorKind::PermissionDenied || is_read_only_filesystem(error) => {
+            println!("File system access denied: {}", error);
+            HttpResponse::forbidden()
+        }
+        _ => {
+            println!("File system error: {}", error);
+            HttpResponse::internal_server_error()
+        }
+    }
+}
+
+pub fn handle_file(request: &HttpRequest, file_name: &str, server_configuration: &ServerConfiguration) -> Result<HttpResponse, std::io::Error> {
+    let file_path = match resolve_in_directory(file_name, server_configuration) {
+        Ok(file_path) => file_path,
+        Err(rejection) => return Ok(rejection.to_response())
+    };
+    let response = if request.method == HttpMethod::Get || request.method == HttpMethod::Head {
+        handle_get_file(request, &file_path, server_configuration)
+    } else if request.method == HttpMethod::Post {
+        handle_post_file(request, &file_path)
+    } else if request.method == HttpMethod::Put {
+        handle_put_file(request, &file_path)
+    } else {
+        Ok(HttpResponse::not_found())
+    };
+    Ok(response.unwrap_or_else(|error| file_error_response(&error)))
+}
+
+pub fn static_file_exists(path: &str, server_configuration: &ServerConfiguration) -> bool {
+    resolve_in_directory(path, server_configuration).is_ok_and(|file_path| file_path.exists())
+}
+
+pub fn handle_static(request: &HttpRequest, path: &str, server_configuration: &ServerConfiguration) -> Result<HttpResponse, std::io::Error> {
+    if request.method != HttpMethod::Get && request.method != HttpMethod::Head {
+        return Ok(HttpResponse::not_found());
+    }
+    let file_path = match resolve_in_directory(path, server_configuration) {
+        Ok(file_path) => file_path,
+        Err(rejection) => return Ok(rejection.to_response())
+    };
+    let response = if file_path.is_dir() {
+        handle_get_directory(request, &file_path, server_configuration)
+    } else if file_path.is_file() && is_hotlink(request, &file_path, server_configuration) {
+        Ok(HttpResponse::forbidden())
+    } else if file_path.is_file() {
+        serve_file(request, &file_path, content_type_for(&file_path), server_configuration.file_cache.as_deref())
+    } else {
+        Ok(HttpResponse::not_found())
+    };
+    Ok(response.unwrap_or_else(|error| file_error_response(&error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_errors_map_to_forbidden() {
+        let error = std::io::Error::from(ErrorKind::PermissionDenied);
+        assert_eq!(file_error_response(&error).status, 403);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_filesystem_maps_to_forbidden() {
+        let error = std::io::Error::from_raw_os_error(READ_ONLY_FILESYSTEM_ERROR);
+        assert_eq!(file_error_response(&error).status, 403);
+    }
+
+    #[test]
+    fn other_errors_map_to_not_found_or_internal_server_error() {
+        assert_eq!(file_error_response(&std::io::Error::from(ErrorKind::NotFound)).status, 404);
+        assert_eq!(file_error_response(&std::io::Error::other("disk on fire")).status, 500);
+    }
+
+    #[test]
+    fn control_characters_in_decoded_segments_are_malformed() {
+        assert!(matches!(resolve_file_path("/srv", "secret%00.txt"), Err(PathRejection::Malformed)));
+        assert!(matches!(resolve_file_path("/srv", "a%0Ab.txt"), Err(PathRejection::Malformed)));
+        assert!(matches!(resolve_file_path("/srv", "a%2Fb.txt"), Err(PathRejection::Malformed)));
+    }
+
+    #[test]
+    fn ordinary_paths_resolve_inside_the_directory() {
+        assert_eq!(resolve_file_path("/srv", "docs/read%20me.txt?v=1").ok(), Some(PathBuf::from("/srv/docs/read me.txt")));
+    }
+}
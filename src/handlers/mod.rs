@@ -0,0 +1,27 @@
+pub mod debug;
+pub mod files;
+
+use crate::config::ServerConfiguration;
+use crate::http::types::{ HttpRequest, HttpResponse };
+
+use files::handle_static;
+
+// Without static content to serve the root stays an empty 200 response
+pub fn handle_root(request: &HttpRequest, server_configuration: &ServerConfiguration) -> Result<HttpResponse, std::io::Error> {
+    if server_configuration.directory.is_some() {
+        let response = handle_static(request, "/", server_configuration)?;
+        if response.status != 404 {
+            return Ok(response);
+        }
+    }
+    Ok(HttpResponse::builder(200).build())
+}
+
+pub fn handle_echo(text: &str) -> Result<HttpResponse, std::io::Error> {
+    Ok(HttpResponse::builder(200).text(text).build())
+}
+
+pub fn handle_user_agent(request: &HttpRequest) -> Result<HttpResponse, std::io::Error> {
+    let user_agent = request.headers.get("User-Agent").unwrap_or("Unknown");
+    Ok(HttpResponse::builder(200).text(user_agent).build())
+}
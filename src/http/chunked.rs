@@ -0,0 +1,36 @@
+use std::io::Write;
+
+/// Frames everything written to it with the chunked transfer coding (RFC 9112, section 7.1),
+/// for bodies whose length is not known when the headers are sent
+pub struct ChunkedWriter<W: Write> {
+    inner: W
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub fn new(inner: W) -> ChunkedWriter<W> {
+        ChunkedWriter { inner }
+    }
+
+    // The zero-length last chunk terminates the body, no trailer fields are sent
+    pub fn finish(mut self) -> Result<W, std::io::Error> {
+        self.inner.write_all(b"0\r\n\r\n")?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        // An empty chunk would end the body prematurely
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:x}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.inner.flush()
+    }
+}
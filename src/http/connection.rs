@@ -0,0 +1,60 @@
+use std::io::{ Cursor, Read, Write };
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// A bidirectional byte stream a request is read from and its response written to.
+/// Streams that cannot bound the time of a single read, e.g. in-memory buffers, keep the default no-op timeout.
+pub trait Connection: Read + Write {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), std::io::Error> {
+        let _ = timeout;
+        Ok(())
+    }
+}
+
+impl Connection for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), std::io::Error> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Connection for std::os::unix::net::UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), std::io::Error> {
+        std::os::unix::net::UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// Reads a request from a fixed input and collects everything written in memory, so that a whole
+/// request and response cycle can run without a socket.
+pub struct MemoryConnection {
+    input: Cursor<Vec<u8>>,
+    output: Vec<u8>
+}
+
+impl MemoryConnection {
+    pub fn new(input: impl Into<Vec<u8>>) -> MemoryConnection {
+        MemoryConnection { input: Cursor::new(input.into()), output: Vec::new() }
+    }
+
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+}
+
+impl Read for MemoryConnection {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.input.read(buf)
+    }
+}
+
+impl Write for MemoryConnection {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+impl Connection for MemoryConnection {}
@@ -0,0 +1,166 @@
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const LONG_DAY_NAMES: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const MONTH_NAMES: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const SECONDS_PER_DAY: u64 = 86400;
+
+// Proleptic Gregorian calendar conversions, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days_since_epoch: i64) -> (i64, u32, u32) {
+    let z = days_since_epoch + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Formats the time as an IMF-fixdate, e.g. "Sun, 06 Nov 1994 08:49:37 GMT" (RFC 7231, section 7.1.1.1)
+pub fn format_http_date(time: SystemTime) -> String {
+    let seconds_since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let days_since_epoch = (seconds_since_epoch / SECONDS_PER_DAY) as i64;
+    let seconds_of_day = seconds_since_epoch % SECONDS_PER_DAY;
+    let (year, month, day) = civil_from_days(days_since_epoch);
+    // 1970-01-01 was a Thursday
+    let day_name = DAY_NAMES[((days_since_epoch + 3) % 7) as usize];
+    format!("{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        day_name, day, MONTH_NAMES[month as usize - 1], year,
+        seconds_of_day / 3600, seconds_of_day % 3600 / 60, seconds_of_day % 60)
+}
+
+fn parse_month(name: &str) -> Option<u32> {
+    MONTH_NAMES.iter().position(|month| *month == name).map(|idx| idx as u32 + 1)
+}
+
+fn parse_time_of_day(value: &str) -> Option<u64> {
+    let mut parts = value.split(':');
+    let hours = parts.next()?.parse::<u64>().ok()?;
+    let minutes = parts.next()?.parse::<u64>().ok()?;
+    let seconds = parts.next()?.parse::<u64>().ok()?;
+    if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+fn to_system_time(year: i64, month: u32, day: u32, seconds_of_day: u64) -> Option<SystemTime> {
+    if !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    let days_since_epoch = days_from_civil(year, month, day);
+    if civil_from_days(days_since_epoch) != (year, month, day) {
+        return None;
+    }
+    let seconds_since_epoch = days_since_epoch as u64 * SECONDS_PER_DAY + seconds_of_day;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds_since_epoch))
+}
+
+// "Sun, 06 Nov 1994 08:49:37 GMT"
+fn parse_imf_fixdate(value: &str) -> Option<SystemTime> {
+    let (day_name, rest) = value.split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    if !DAY_NAMES.contains(&day_name) || parts.len() != 5 || parts[4] != "GMT" || parts[0].len() != 2 || parts[2].len() != 4 {
+        return None;
+    }
+    let day = parts[0].parse::<u32>().ok()?;
+    let month = parse_month(parts[1])?;
+    let year = parts[2].parse::<i64>().ok()?;
+    to_system_time(year, month, day, parse_time_of_day(parts[3])?)
+}
+
+// "Sunday, 06-Nov-94 08:49:37 GMT"
+fn parse_rfc850_date(value: &str) -> Option<SystemTime> {
+    let (day_name, rest) = value.split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    if !LONG_DAY_NAMES.contains(&day_name) || parts.len() != 3 || parts[2] != "GMT" {
+        return None;
+    }
+    let date_parts: Vec<&str> = parts[0].split('-').collect();
+    if date_parts.len() != 3 || date_parts[2].len() != 2 {
+        return None;
+    }
+    let day = date_parts[0].parse::<u32>().ok()?;
+    let month = parse_month(date_parts[1])?;
+    // Two digit years which appear to be more than 50 years in the future are in the past, RFC 7231, section 7.1.1.1
+    let two_digit_year = date_parts[2].parse::<i64>().ok()?;
+    let year = if two_digit_year < 70 { 2000 + two_digit_year } else { 1900 + two_digit_year };
+    to_system_time(year, month, day, parse_time_of_day(parts[1])?)
+}
+
+// "Sun Nov  6 08:49:37 1994"
+fn parse_asctime_date(value: &str) -> Option<SystemTime> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() != 5 || !DAY_NAMES.contains(&parts[0]) {
+        return None;
+    }
+    let month = parse_month(parts[1])?;
+    let day = parts[2].parse::<u32>().ok()?;
+    let year = parts[4].parse::<i64>().ok()?;
+    to_system_time(year, month, day, parse_time_of_day(parts[3])?)
+}
+
+/// Parses any of the three HTTP-date formats recipients are required to accept (RFC 7231, section 7.1.1.1)
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    parse_imf_fixdate(value)
+        .or_else(|| parse_rfc850_date(value))
+        .or_else(|| parse_asctime_date(value))
+}
+
+/// HTTP-dates have a resolution of one second, so the sub-second part is dropped before comparing times
+pub fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    let seconds_since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    UNIX_EPOCH + Duration::from_secs(seconds_since_epoch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sun, 06 Nov 1994 08:49:37 GMT, the example from RFC 7231
+    fn example_time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(784111777)
+    }
+
+    #[test]
+    fn time_is_formatted_as_imf_fixdate() {
+        assert_eq!(format_http_date(example_time()), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(format_http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(format_http_date(UNIX_EPOCH + Duration::from_secs(951782400)), "Tue, 29 Feb 2000 00:00:00 GMT");
+    }
+
+    #[test]
+    fn all_three_formats_are_parsed() {
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(example_time()));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(example_time()));
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(example_time()));
+    }
+
+    #[test]
+    fn formatted_date_parses_back() {
+        let now = truncate_to_seconds(SystemTime::now());
+        assert_eq!(parse_http_date(&format_http_date(now)), Some(now));
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        assert_eq!(parse_http_date("Sun, 31 Nov 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 24:00:00 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+}
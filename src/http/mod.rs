@@ -0,0 +1,6 @@
+pub mod chunked;
+pub mod connection;
+pub mod date;
+pub mod parser;
+pub mod types;
+pub mod uri;
@@ -0,0 +1,401 @@
+use std::io::{ BufRead, BufReader, Error, ErrorKind, Read, Write };
+use std::str::FromStr;
+use std::time::{ Duration, Instant };
+
+use crate::http::connection::Connection;
+use crate::http::types::{ HttpHeaders, HttpMethod, HttpRequest, HttpResponse, RequestTarget };
+
+const MAX_HEADER_BYTES: usize = 8192;
+const MAX_HEADER_COUNT: usize = 100;
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const BODY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct RequestLine {
+    method: HttpMethod,
+    uri: String,
+    target: RequestTarget,
+    http_version: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    #[error("Timed out while reading request headers")]
+    HeaderTimeout,
+    #[error("Timed out while reading the request body")]
+    BodyTimeout,
+    #[error("Request header fields are too large")]
+    HeaderFieldsTooLarge,
+    #[error("Request needs {0} bytes, more than the allowed request memory")]
+    MemoryLimitExceeded(usize),
+    #[error("Conflicting message framing: {0}")]
+    ConflictingFraming(&'static str),
+    #[error("Transfer-Encoding '{0}' is not supported")]
+    UnsupportedTransferCoding(String),
+    #[error("Connection closed or idle before a request was sent")]
+    NoRequest
+}
+
+impl RequestError {
+    pub fn from_io_error(error: &Error) -> Option<&RequestError> {
+        error.get_ref().and_then(|inner| inner.downcast_ref::<RequestError>())
+    }
+
+    pub fn to_response(&self) -> HttpResponse {
+        match self {
+            RequestError::HeaderTimeout | RequestError::BodyTimeout | RequestError::NoRequest => HttpResponse::request_timeout(),
+            RequestError::HeaderFieldsTooLarge => HttpResponse::request_header_fields_too_large(),
+            RequestError::MemoryLimitExceeded(_) => HttpResponse::payload_too_large(),
+            RequestError::ConflictingFraming(_) => closing_connection(HttpResponse::bad_request()),
+            RequestError::UnsupportedTransferCoding(_) => closing_connection(HttpResponse::builder(501).build())
+        }
+    }
+}
+
+// The end of the rejected request is unknown, so nothing else on the connection can be trusted
+fn closing_connection(mut response: HttpResponse) -> HttpResponse {
+    response.headers.append(String::from("Connection"), String::from("close"));
+    response
+}
+
+impl From<RequestError> for Error {
+    fn from(error: RequestError) -> Error {
+        Error::other(error)
+    }
+}
+
+// Bounds the request line and headers both in size and in the total time a client may take to send them
+struct HeaderReadBudget {
+    deadline: Instant,
+    remaining_bytes: usize
+}
+
+impl HeaderReadBudget {
+    fn new() -> HeaderReadBudget {
+        HeaderReadBudget {
+            deadline: Instant::now() + HEADER_READ_TIMEOUT,
+            remaining_bytes: MAX_HEADER_BYTES
+        }
+    }
+}
+
+fn read_header_line<C: Connection>(reader: &mut BufReader<C>, budget: &mut HeaderReadBudget) -> Result<String, std::io::Error> {
+    let mut line: Vec<u8> = Vec::new();
+    loop {
+        let time_left = budget.deadline.saturating_duration_since(Instant::now());
+        if time_left.is_zero() {
+            return Err(RequestError::HeaderTimeout.into());
+        }
+        reader.get_ref().set_read_timeout(Some(time_left))?;
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut =>
+                return Err(RequestError::HeaderTimeout.into()),
+            Err(e) => return Err(e)
+        };
+        if available.is_empty() {
+            break;
+        }
+        let (consumed, line_complete) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(newline_idx) => (newline_idx + 1, true),
+            None => (available.len(), false)
+        };
+        if consumed > budget.remaining_bytes {
+            return Err(RequestError::HeaderFieldsTooLarge.into());
+        }
+        budget.remaining_bytes -= consumed;
+        line.extend_from_slice(&available[..consumed]);
+        reader.consume(consumed);
+        if line_complete {
+            break;
+        }
+    }
+    String::from_utf8(line).map_err(|_| Error::other("Malformed HTTP request: request line or header is not valid UTF-8"))
+}
+
+fn parse_request_line<C: Connection>(reader: &mut BufReader<C>, budget: &mut HeaderReadBudget) -> Result<RequestLine, std::io::Error> {
+    // Running out of time or reaching the end of the stream before the first byte is how a persistent connection ends
+    let request_line = match read_header_line(reader, budget) {
+        Err(e) if matches!(RequestError::from_io_error(&e), Some(RequestError::HeaderTimeout)) && budget.remaining_bytes == MAX_HEADER_BYTES =>
+            return Err(RequestError::NoRequest.into()),
+        result => result?
+    };
+    if request_line.is_empty() {
+        return Err(RequestError::NoRequest.into());
+    }
+    let request_line_parts: Vec<&str> = request_line.split_whitespace().collect();
+    let method_input =  *request_line_parts.first()
+        .ok_or(Error::other(format!("Malformed HTTP request: cannot parse HTTP method: '{}'", request_line)))?;
+    let method = HttpMethod::from_str(method_input).map_err(|err| Error::other(format!("Malformed HTTP request: cannot parse HTTP method: '{}'", err)))?;
+    let uri =  String::from(*request_line_parts.get(1)
+        .ok_or(Error::other(format!("Malformed HTTP request: cannot parse request URI: '{}'", request_line)))?);
+    let http_version =  String::from(*request_line_parts.get(2)
+        .ok_or(Error::other(format!("Malformed HTTP request: cannot parse request HTTP version: '{}'", request_line)))?);
+    let target = RequestTarget::parse(&method, &uri)?;
+    Ok(RequestLine {
+        method,
+        uri,
+        target,
+        http_version
+    })
+}
+
+fn parse_http_headers<C: Connection>(reader: &mut BufReader<C>, budget: &mut HeaderReadBudget) -> Result<HttpHeaders, std::io::Error> {
+    let mut name_value_pairs: Vec<(String, String)> = Vec::new();
+    loop {
+        let current_header_line = read_header_line(reader, budget)?;
+        if current_header_line.is_empty() || current_header_line == "\r\n" {
+            break;
+        }
+        if name_value_pairs.len() == MAX_HEADER_COUNT {
+            return Err(RequestError::HeaderFieldsTooLarge.into());
+        }
+        let header_parts = current_header_line
+            .split_once(":").ok_or(Error::other(format!("Malformed HTTP header: '{}'", current_header_line)))?;
+        let header = (String::from(header_parts.0.trim()), String::from(header_parts.1.trim()));
+        name_value_pairs.push(header);
+    }
+    Ok(HttpHeaders::new(name_value_pairs))
+}
+
+// A body length that two parties could read differently is the basis of request smuggling,
+// so Content-Length next to Transfer-Encoding and differing repeated Content-Length values are rejected.
+// Request bodies are never decoded from a transfer coding, so any Transfer-Encoding is rejected as well,
+// otherwise a chunked body would be read as the next request on the connection.
+fn get_content_length_from_headers(http_headers: &HttpHeaders) -> Result<usize, std::io::Error> {
+    let content_lengths: Vec<&str> = http_headers.get_all("Content-Length").iter()
+        .flat_map(|content_length| content_length.split(','))
+        .map(|content_length| content_length.trim())
+        .collect();
+    if !content_lengths.is_empty() && http_headers.contains("Transfer-Encoding") {
+        return Err(RequestError::ConflictingFraming("both Content-Length and Transfer-Encoding are present").into());
+    }
+    if content_lengths.iter().any(|content_length| *content_length != content_lengths[0]) {
+        return Err(RequestError::ConflictingFraming("differing Content-Length values").into());
+    }
+    if let Some(transfer_encoding) = http_headers.get("Transfer-Encoding") {
+        return Err(RequestError::UnsupportedTransferCoding(String::from(transfer_encoding)).into());
+    }
+    let content_length_header_value = content_lengths.first().copied().unwrap_or("0");
+    let content_length = content_length_header_value.parse::<usize>()
+        .map_err(|_| Error::other(format!("Could not parse Content-Length header value '{}'", content_length_header_value)))?;
+    Ok(content_length)
+}
+
+/// Waits for the first byte of the next request on a connection. Returns false if the connection was closed,
+/// or stayed idle for longer than a client may take to send the request headers.
+pub fn wait_for_request<C: Connection>(reader: &mut BufReader<C>) -> Result<bool, std::io::Error> {
+    if !reader.buffer().is_empty() {
+        return Ok(true);
+    }
+    reader.get_ref().set_read_timeout(Some(HEADER_READ_TIMEOUT))?;
+    match reader.fill_buf() {
+        Ok(available) => Ok(!available.is_empty()),
+        Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => Ok(false),
+        Err(e) => Err(e)
+    }
+}
+
+// The request side of the --max-request-memory budget is enforced here, before the request is dispatched,
+// so that a rejected request never has side effects. The body is only read once the request is known to fit.
+// The reader is kept across the requests of a persistent connection, so that pipelined bytes are not lost.
+pub fn parse_request<C: Connection>(reader: &mut BufReader<C>, max_request_memory: Option<usize>) -> Result<HttpRequest, std::io::Error> {
+    let mut header_read_budget = HeaderReadBudget::new();
+    let request_line = parse_request_line(reader, &mut header_read_budget)?;
+    let http_headers = parse_http_headers(reader, &mut header_read_budget)?;
+    let content_length = get_content_length_from_headers(&http_headers)?;
+    let header_bytes = MAX_HEADER_BYTES - header_read_budget.remaining_bytes;
+    let request_bytes = header_bytes.saturating_add(content_length);
+    if max_request_memory.is_some_and(|max_bytes| request_bytes > max_bytes) {
+        return Err(RequestError::MemoryLimitExceeded(request_bytes).into());
+    }
+    // HTTP/1.0 clients do not know interim responses
+    if content_length > 0 && request_line.http_version != "HTTP/1.0" && expects_continue(&http_headers) {
+        send_continue(reader.get_mut())?;
+    }
+    let body = read_body(reader, content_length)?;
+    Ok(HttpRequest {
+        method: request_line.method,
+        uri: request_line.uri,
+        target: request_line.target,
+        http_version: request_line.http_version,
+        headers: http_headers,
+        body
+    })
+}
+
+// A client that announces a body and then stalls would otherwise hold the connection forever
+fn read_body<C: Connection>(reader: &mut BufReader<C>, content_length: usize) -> Result<Vec<u8>, std::io::Error> {
+    let deadline = Instant::now() + BODY_READ_TIMEOUT;
+    let mut body: Vec<u8> = vec![0; content_length];
+    let mut filled = 0;
+    while filled < content_length {
+        let time_left = deadline.saturating_duration_since(Instant::now());
+        if time_left.is_zero() {
+            return Err(RequestError::BodyTimeout.into());
+        }
+        reader.get_ref().set_read_timeout(Some(time_left))?;
+        match reader.read(&mut body[filled..]) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut =>
+                return Err(RequestError::BodyTimeout.into()),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(body)
+}
+
+fn expects_continue(http_headers: &HttpHeaders) -> bool {
+    http_headers.get("Expect").is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+}
+
+// Sent right before the body is read, which happens exactly once per request
+fn send_continue<W: Write>(writer: &mut W) -> Result<(), std::io::Error> {
+    HttpResponse::continue_interim().write_to(writer)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::http::connection::MemoryConnection;
+
+    // Delivers its input and then behaves like a socket whose read timeout expired
+    struct StalledConnection {
+        input: Cursor<Vec<u8>>
+    }
+
+    impl Read for StalledConnection {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+            match self.input.read(buf)? {
+                0 => Err(ErrorKind::WouldBlock.into()),
+                read => Ok(read)
+            }
+        }
+    }
+
+    impl Write for StalledConnection {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), std::io::Error> {
+            Ok(())
+        }
+    }
+
+    impl Connection for StalledConnection {}
+
+    fn request_error<C: Connection>(connection: C) -> RequestError {
+        let error = parse_request(&mut BufReader::new(connection), None).expect_err("the request should be rejected");
+        match error.into_inner().map(|inner| inner.downcast::<RequestError>()) {
+            Some(Ok(request_error)) => *request_error,
+            other => panic!("not a request error: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn complete_request_is_parsed() {
+        let connection = MemoryConnection::new("POST /files/a.txt HTTP/1.1\r\nHost: localhost\r\nContent-Length: 3\r\n\r\nabc");
+        let request = parse_request(&mut BufReader::new(connection), None).unwrap();
+        assert_eq!(request.method, HttpMethod::Post);
+        assert_eq!(request.uri, "/files/a.txt");
+        assert_eq!(request.headers.get("host"), Some("localhost"));
+        assert_eq!(request.body, b"abc");
+    }
+
+    #[test]
+    fn oversized_header_block_is_rejected_with_431() {
+        let input = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(MAX_HEADER_BYTES));
+        let request_error = request_error(MemoryConnection::new(input));
+        assert!(matches!(request_error, RequestError::HeaderFieldsTooLarge));
+        assert_eq!(request_error.to_response().status, 431);
+    }
+
+    #[test]
+    fn too_many_headers_are_rejected_with_431() {
+        let headers: String = (0..=MAX_HEADER_COUNT).map(|idx| format!("X-Header-{}: {}\r\n", idx, idx)).collect();
+        let request_error = request_error(MemoryConnection::new(format!("GET / HTTP/1.1\r\n{}\r\n", headers)));
+        assert!(matches!(request_error, RequestError::HeaderFieldsTooLarge));
+    }
+
+    #[test]
+    fn headers_without_the_terminating_blank_line_time_out_with_408() {
+        let input = b"GET / HTTP/1.1\r\nHost: localhost\r\n".to_vec();
+        let request_error = request_error(StalledConnection { input: Cursor::new(input) });
+        assert!(matches!(request_error, RequestError::HeaderTimeout));
+        assert_eq!(request_error.to_response().status, 408);
+    }
+
+    #[test]
+    fn idle_connection_is_not_a_request() {
+        let request_error = request_error(StalledConnection { input: Cursor::new(Vec::new()) });
+        assert!(matches!(request_error, RequestError::NoRequest));
+    }
+
+    fn headers(name_value_pairs: &[(&str, &str)]) -> HttpHeaders {
+        HttpHeaders::new(name_value_pairs.iter().map(|(name, value)| (String::from(*name), String::from(*value))).collect())
+    }
+
+    fn framing_error(http_headers: &HttpHeaders) -> RequestError {
+        let error = get_content_length_from_headers(http_headers).expect_err("framing should be rejected");
+        match error.into_inner().map(|inner| inner.downcast::<RequestError>()) {
+            Some(Ok(request_error)) => *request_error,
+            other => panic!("not a request error: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn no_framing_headers_mean_no_body() {
+        assert_eq!(get_content_length_from_headers(&headers(&[("Host", "localhost")])).unwrap(), 0);
+    }
+
+    #[test]
+    fn repeated_identical_content_lengths_are_accepted() {
+        let http_headers = headers(&[("Content-Length", "5"), ("Content-Length", "5, 5")]);
+        assert_eq!(get_content_length_from_headers(&http_headers).unwrap(), 5);
+    }
+
+    #[test]
+    fn differing_content_lengths_are_rejected() {
+        let http_headers = headers(&[("Content-Length", "5"), ("Content-Length", "6")]);
+        assert!(matches!(framing_error(&http_headers), RequestError::ConflictingFraming(_)));
+        let http_headers = headers(&[("Content-Length", "5, 6")]);
+        assert!(matches!(framing_error(&http_headers), RequestError::ConflictingFraming(_)));
+    }
+
+    #[test]
+    fn content_length_with_transfer_encoding_is_rejected() {
+        let http_headers = headers(&[("Content-Length", "5"), ("Transfer-Encoding", "chunked")]);
+        assert!(matches!(framing_error(&http_headers), RequestError::ConflictingFraming(_)));
+        let http_headers = headers(&[("transfer-encoding", "gzip, chunked"), ("content-length", "5")]);
+        assert!(matches!(framing_error(&http_headers), RequestError::ConflictingFraming(_)));
+    }
+
+    #[test]
+    fn bare_transfer_encoding_is_rejected() {
+        let http_headers = headers(&[("Transfer-Encoding", "chunked")]);
+        assert!(matches!(framing_error(&http_headers), RequestError::UnsupportedTransferCoding(coding) if coding == "chunked"));
+    }
+
+    #[test]
+    fn framing_errors_close_the_connection() {
+        let conflicting = RequestError::ConflictingFraming("differing Content-Length values").to_response();
+        assert_eq!(conflicting.status, 400);
+        assert_eq!(conflicting.headers.get("Connection"), Some("close"));
+        let unsupported = RequestError::UnsupportedTransferCoding(String::from("chunked")).to_response();
+        assert_eq!(unsupported.status, 501);
+        assert_eq!(unsupported.headers.get("Connection"), Some("close"));
+    }
+
+    #[test]
+    fn body_shorter_than_its_content_length_times_out_with_408() {
+        let input = b"POST /files/a.txt HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc".to_vec();
+        let request_error = request_error(StalledConnection { input: Cursor::new(input) });
+        assert!(matches!(request_error, RequestError::BodyTimeout));
+        assert_eq!(request_error.to_response().status, 408);
+    }
+}
@@ -0,0 +1,500 @@
+use std::io::{ Error, Read, Write };
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::http::chunked::ChunkedWriter;
+
+#[derive(Debug, PartialEq)]
+pub enum HttpMethod {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options
+}
+
+impl HttpMethod {
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Connect => "CONNECT",
+            HttpMethod::Options => "OPTIONS"
+        }
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+      match s.to_uppercase().as_str() {
+        "GET" => Ok(HttpMethod::Get),
+        "HEAD" => Ok(HttpMethod::Head),
+        "POST" => Ok(HttpMethod::Post),
+        "PUT" => Ok(HttpMethod::Put),
+        "DELETE" => Ok(HttpMethod::Delete),
+        "CONNECT" => Ok(HttpMethod::Connect),
+        "OPTIONS" => Ok(HttpMethod::Options),
+        _ => Err("Unknown HTTP method"),
+      }
+    }
+  }
+
+// The request-target forms from RFC 7230, section 5.3
+#[derive(Debug, PartialEq)]
+pub enum RequestTarget {
+    Origin(String),
+    Absolute { authority: String, path: String },
+    Authority(String),
+    Asterisk
+}
+
+impl RequestTarget {
+    pub fn parse(method: &HttpMethod, uri: &str) -> Result<RequestTarget, std::io::Error> {
+        if *method == HttpMethod::Connect {
+            return RequestTarget::parse_authority(uri)
+                .map(|authority| RequestTarget::Authority(String::from(authority)))
+                .ok_or(Error::other(format!("Malformed HTTP request: CONNECT target is not in authority-form: '{}'", uri)));
+        }
+        if uri == "*" {
+            Ok(RequestTarget::Asterisk)
+        } else if uri.starts_with('/') {
+            Ok(RequestTarget::Origin(String::from(uri)))
+        } else if let Some((_, without_scheme)) = uri.split_once("://") {
+            let path_start = without_scheme.find(['/', '?']).unwrap_or(without_scheme.len());
+            let (authority, path) = without_scheme.split_at(path_start);
+            if authority.is_empty() {
+                return Err(Error::other(format!("Malformed HTTP request: absolute URI without authority: '{}'", uri)));
+            }
+            let path = if path.starts_with('/') { String::from(path) } else { format!("/{}", path) };
+            Ok(RequestTarget::Absolute { authority: String::from(authority), path })
+        } else {
+            Err(Error::other(format!("Malformed HTTP request: cannot parse request target: '{}'", uri)))
+        }
+    }
+
+    fn parse_authority(uri: &str) -> Option<&str> {
+        let (host, port) = uri.rsplit_once(':')?;
+        let valid_host = !host.is_empty() && !host.contains(['/', '?', '#', '@']);
+        let valid_port = !port.is_empty() && port.parse::<u16>().is_ok();
+        if valid_host && valid_port { Some(uri) } else { None }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            RequestTarget::Origin(path) | RequestTarget::Absolute { path, .. } => Some(path.as_str()),
+            RequestTarget::Authority(_) | RequestTarget::Asterisk => None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub uri: String,
+    pub target: RequestTarget,
+    pub http_version: String,
+    pub headers: HttpHeaders,
+    pub body: Vec<u8>
+}
+
+impl HttpRequest {
+    // Approximates the memory held by the parsed request, for accounting against --max-request-memory
+    pub fn allocated_bytes(&self) -> usize {
+        self.uri.len() + self.http_version.len() + self.headers.allocated_bytes() + self.body.len()
+    }
+}
+
+// Every value of a header is kept under the name it was first seen with. Headers keep the order in
+// which their names first appeared, the values of a repeated header the order they were added in.
+#[derive(Debug)]
+pub struct HttpHeaders {
+    entries: Vec<(String, Vec<String>)>
+}
+
+impl HttpHeaders {
+    pub fn new(name_value_pairs: Vec<(String, String)>) -> HttpHeaders {
+        let mut headers = HttpHeaders { entries: Vec::new() };
+        for (name, value) in name_value_pairs {
+            headers.append(name, value);
+        }
+        headers
+    }
+
+    // Header names are case-insensitive and a header may be repeated, the first occurrence wins
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).first().map(String::as_str)
+    }
+
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.entries.iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+
+    pub fn append(&mut self, name: String, value: String) {
+        match self.entries.iter_mut().find(|(header_name, _)| header_name.eq_ignore_ascii_case(&name)) {
+            Some((_, values)) => values.push(value),
+            None => self.entries.push((name, vec![value]))
+        }
+    }
+
+    // One pair per value, so that a repeated header is written as separate lines
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter()
+            .flat_map(|(name, values)| values.iter().map(move |value| (name.as_str(), value.as_str())))
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        self.iter().map(|(name, value)| name.len() + value.len()).sum()
+    }
+
+    pub fn empty() -> HttpHeaders {
+        HttpHeaders::new(Vec::new())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn set(&mut self, name: &str, value: String) {
+        self.remove(name);
+        self.append(String::from(name), value);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.entries.retain(|(header_name, _)| !header_name.eq_ignore_ascii_case(name));
+    }
+}
+
+/// A body held in memory, either owned by the response or shared with e.g. the file cache,
+/// so that serving cached contents does not copy them
+#[derive(Clone, Debug)]
+pub enum Body {
+    Owned(Vec<u8>),
+    Shared(Arc<[u8]>)
+}
+
+impl Default for Body {
+    fn default() -> Body {
+        Body::Owned(Vec::new())
+    }
+}
+
+impl Deref for Body {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Body::Owned(bytes) => bytes,
+            Body::Shared(bytes) => bytes
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Body {
+        Body::Owned(bytes)
+    }
+}
+
+impl From<Arc<[u8]>> for Body {
+    fn from(bytes: Arc<[u8]>) -> Body {
+        Body::Shared(bytes)
+    }
+}
+
+/// A body that is copied to the client when the response is written instead of being held in memory
+pub struct StreamedBody {
+    pub reader: Box<dyn Read + Send>,
+    pub gzip_level: Option<u32>
+}
+
+pub struct HttpResponse {
+    pub http_version: String,
+    pub status: u16,
+    pub reason_phrase: String,
+    pub headers: HttpHeaders,
+    pub body: Body,
+    pub streamed_body: Option<StreamedBody>
+}
+
+/// The canonical reason phrase for a status code, empty for codes the server never sends
+pub fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        503 => "Service Unavailable",
+        505 => "HTTP Version Not Supported",
+        _ => ""
+    }
+}
+
+pub struct HttpResponseBuilder {
+    status: u16,
+    headers: HttpHeaders,
+    body: Body,
+    streamed_body: Option<StreamedBody>
+}
+
+impl HttpResponseBuilder {
+    pub fn header(mut self, name: &str, value: &str) -> HttpResponseBuilder {
+        self.headers.append(String::from(name), String::from(value));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Body>) -> HttpResponseBuilder {
+        self.body = body.into();
+        self
+    }
+
+    pub fn stream(mut self, reader: Box<dyn Read + Send>, content_length: u64) -> HttpResponseBuilder {
+        self.streamed_body = Some(StreamedBody { reader, gzip_level: None });
+        self.header("Content-Length", &content_length.to_string())
+    }
+
+    pub fn text(self, body: &str) -> HttpResponseBuilder {
+        self.header("Content-Type", "text/plain").body(body.as_bytes().to_vec())
+    }
+
+    pub fn json(self, body: &str) -> HttpResponseBuilder {
+        self.header("Content-Type", "application/json").body(body.as_bytes().to_vec())
+    }
+
+    // Content-Length is derived from the body unless it was set explicitly, a 204 must not carry one
+    pub fn build(mut self) -> HttpResponse {
+        if !self.headers.contains("Content-Length") && self.status != 204 {
+            self.headers.append(String::from("Content-Length"), self.body.len().to_string());
+        }
+        HttpResponse {
+            body: self.body,
+            streamed_body: self.streamed_body,
+            ..HttpResponse::without_body(self.status, self.headers)
+        }
+    }
+}
+
+impl HttpResponse {
+    // Every response is created through here, so that the reason phrase always matches the status
+    fn without_body(status: u16, headers: HttpHeaders) -> HttpResponse {
+        HttpResponse {
+            http_version: String::from("HTTP/1.1"),
+            status,
+            reason_phrase: String::from(reason_phrase(status)),
+            headers,
+            body: Body::default(),
+            streamed_body: None
+        }
+    }
+
+    pub fn builder(status: u16) -> HttpResponseBuilder {
+        HttpResponseBuilder {
+            status,
+            headers: HttpHeaders::empty(),
+            body: Body::default(),
+            streamed_body: None
+        }
+    }
+
+    pub fn continue_interim() -> HttpResponse {
+        HttpResponse::without_body(100, HttpHeaders::empty())
+    }
+
+    pub fn not_modified(etag: &str) -> HttpResponse {
+        HttpResponse::without_body(304, HttpHeaders::new(vec![
+            (String::from("ETag"), String::from(etag))
+        ]))
+    }
+
+    pub fn request_timeout() -> HttpResponse {
+        HttpResponse::without_body(408, HttpHeaders::new(vec![
+            (String::from("Connection"), String::from("close"))
+        ]))
+    }
+
+    pub fn payload_too_large() -> HttpResponse {
+        HttpResponse::without_body(413, HttpHeaders::new(vec![
+            (String::from("Connection"), String::from("close"))
+        ]))
+    }
+
+    pub fn request_header_fields_too_large() -> HttpResponse {
+        HttpResponse::without_body(431, HttpHeaders::new(vec![
+            (String::from("Connection"), String::from("close"))
+        ]))
+    }
+
+    pub fn bad_request() -> HttpResponse {
+        HttpResponse::without_body(400, HttpHeaders::empty())
+    }
+
+    pub fn unauthorized(realm: &str) -> HttpResponse {
+        HttpResponse::without_body(401, HttpHeaders::new(vec![
+            (String::from("WWW-Authenticate"), format!("Basic realm=\"{}\"", realm))
+        ]))
+    }
+
+    pub fn not_found() -> HttpResponse {
+        HttpResponse::without_body(404, HttpHeaders::empty())
+    }
+
+    pub fn forbidden() -> HttpResponse {
+        HttpResponse::without_body(403, HttpHeaders::empty())
+    }
+
+    pub fn internal_server_error() -> HttpResponse {
+        HttpResponse::without_body(500, HttpHeaders::empty())
+    }
+
+    fn format_status_line_and_headers(&self) -> String {
+        let mut formatted_headers = String::new();
+        for (name, value) in self.headers.iter() {
+            formatted_headers.push_str(format!("{}: {}\r\n", name, value).as_str());
+        }
+        format!("{} {} {}\r\n{}\r\n", self.http_version.as_str(), self.status, self.reason_phrase, formatted_headers.as_str())
+    }
+
+    // A streamed body is gzip-compressed on the fly and sent chunked, as its compressed length is only known at the end
+    pub fn write_to<W: Write>(&mut self, writer: &mut W) -> Result<(), std::io::Error> {
+        writer.write_all(self.format_status_line_and_headers().as_bytes())?;
+        match self.streamed_body.take() {
+            Some(StreamedBody { mut reader, gzip_level: Some(gzip_level) }) => {
+                let mut encoder = GzEncoder::new(ChunkedWriter::new(&mut *writer), Compression::new(gzip_level));
+                std::io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?.finish()?;
+                Ok(())
+            }
+            Some(StreamedBody { mut reader, gzip_level: None }) => {
+                std::io::copy(&mut reader, writer)?;
+                Ok(())
+            }
+            None => writer.write_all(&self.body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connect_target_is_parsed_in_authority_form() {
+        let target = RequestTarget::parse(&HttpMethod::Connect, "example.com:443").unwrap();
+        assert_eq!(target, RequestTarget::Authority(String::from("example.com:443")));
+        assert_eq!(target.path(), None);
+    }
+
+    #[test]
+    fn connect_target_without_port_or_with_path_is_rejected() {
+        assert!(RequestTarget::parse(&HttpMethod::Connect, "example.com").is_err());
+        assert!(RequestTarget::parse(&HttpMethod::Connect, "example.com:https").is_err());
+        assert!(RequestTarget::parse(&HttpMethod::Connect, "/index.html").is_err());
+    }
+
+    #[test]
+    fn other_target_forms_are_parsed() {
+        assert_eq!(RequestTarget::parse(&HttpMethod::Options, "*").unwrap(), RequestTarget::Asterisk);
+        assert_eq!(RequestTarget::parse(&HttpMethod::Get, "/echo/abc").unwrap().path(), Some("/echo/abc"));
+        let absolute = RequestTarget::parse(&HttpMethod::Get, "http://example.com?x=1").unwrap();
+        assert_eq!(absolute, RequestTarget::Absolute { authority: String::from("example.com"), path: String::from("/?x=1") });
+    }
+
+    #[test]
+    fn content_length_is_derived_from_the_body() {
+        let response = HttpResponse::builder(200).text("hello").build();
+        assert_eq!(response.headers.get("Content-Length"), Some("5"));
+        assert_eq!(response.headers.get("Content-Type"), Some("text/plain"));
+        let empty = HttpResponse::builder(200).build();
+        assert_eq!(empty.headers.get("Content-Length"), Some("0"));
+    }
+
+    #[test]
+    fn explicit_content_length_is_preserved() {
+        let response = HttpResponse::builder(200).header("Content-Length", "42").body(b"short".to_vec()).build();
+        assert_eq!(response.headers.get_all("Content-Length"), vec!["42"]);
+    }
+
+    #[test]
+    fn no_content_has_no_content_length() {
+        let response = HttpResponse::builder(204).build();
+        assert_eq!(response.headers.get("Content-Length"), None);
+        assert_eq!(response.reason_phrase, "No Content");
+    }
+
+    #[test]
+    fn repeated_headers_survive_serialization() {
+        let mut response = HttpResponse::builder(200)
+            .header("Set-Cookie", "session=abc; HttpOnly")
+            .header("Set-Cookie", "theme=dark")
+            .build();
+
+        let mut written: Vec<u8> = Vec::new();
+        response.write_to(&mut written).unwrap();
+
+        let written = String::from_utf8(written).unwrap();
+        let set_cookies: Vec<&str> = written.lines().filter_map(|line| line.strip_prefix("Set-Cookie: ")).collect();
+        assert_eq!(set_cookies, vec!["session=abc; HttpOnly", "theme=dark"]);
+        assert_eq!(response.headers.get_all("set-cookie"), vec!["session=abc; HttpOnly", "theme=dark"]);
+        assert_eq!(response.headers.get("Set-Cookie"), Some("session=abc; HttpOnly"));
+    }
+
+    #[test]
+    fn set_replaces_every_occurrence() {
+        let mut headers = HttpHeaders::new(vec![
+            (String::from("Vary"), String::from("Accept")),
+            (String::from("vary"), String::from("Origin"))
+        ]);
+        headers.set("Vary", String::from("Accept-Encoding"));
+        assert_eq!(headers.get_all("Vary"), vec!["Accept-Encoding"]);
+    }
+
+    #[test]
+    fn common_statuses_have_their_standard_reason_phrase() {
+        let expected = [
+            (200, "OK"),
+            (201, "Created"),
+            (204, "No Content"),
+            (304, "Not Modified"),
+            (400, "Bad Request"),
+            (403, "Forbidden"),
+            (404, "Not Found"),
+            (405, "Method Not Allowed"),
+            (413, "Payload Too Large"),
+            (500, "Internal Server Error")
+        ];
+        for (status, phrase) in expected {
+            assert_eq!(reason_phrase(status), phrase, "status {}", status);
+        }
+    }
+
+    #[test]
+    fn status_line_carries_the_reason_phrase() {
+        let mut written: Vec<u8> = Vec::new();
+        HttpResponse::builder(405).build().write_to(&mut written).unwrap();
+        assert!(written.starts_with(b"HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
@@ -0,0 +1,106 @@
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None
+    }
+}
+
+/// Decodes %XX escapes, returns None for malformed escapes or when the result is not valid UTF-8
+pub fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let high = hex_value(*bytes.get(idx + 1)?)?;
+            let low = hex_value(*bytes.get(idx + 2)?)?;
+            decoded.push(high << 4 | low);
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Percent-encodes everything but the unreserved characters, so that the result stays a single path segment
+pub fn percent_encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    encoded
+}
+
+/// Collapses repeated slashes and drops `.` segments, `..` is kept as is for file resolution to reject
+pub fn normalize_path(uri: &str) -> String {
+    let (path, query) = match uri.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (uri, None)
+    };
+    let segments: Vec<&str> = path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    let mut normalized = format!("/{}", segments.join("/"));
+    if !segments.is_empty() && (path.ends_with('/') || path.ends_with("/.")) {
+        normalized.push('/');
+    }
+    if let Some(query) = query {
+        normalized.push('?');
+        normalized.push_str(query);
+    }
+    normalized
+}
+
+/// Splits the query string of a URI into decoded name-value pairs, `+` stands for a space.
+/// Returns None if any name or value is not properly percent-encoded.
+pub fn parse_query(uri: &str) -> Option<Vec<(String, String)>> {
+    let query = match uri.split_once('?') {
+        Some((_, query)) => query,
+        None => return Some(Vec::new())
+    };
+    query.split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            Some((percent_decode(&name.replace('+', " "))?, percent_decode(&value.replace('+', " "))?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_characters_are_encoded_in_a_segment() {
+        assert_eq!(percent_encode_segment("a b#c?d%e/f.txt"), "a%20b%23c%3Fd%25e%2Ff.txt");
+        assert_eq!(percent_encode_segment("r\u{e9}sum\u{e9}-1_~"), "r%C3%A9sum%C3%A9-1_~");
+        assert_eq!(percent_decode(&percent_encode_segment("50% off?")).as_deref(), Some("50% off?"));
+    }
+
+    #[test]
+    fn repeated_slashes_are_collapsed() {
+        assert_eq!(normalize_path("//files///a.txt"), "/files/a.txt");
+        assert_eq!(normalize_path("/docs//"), "/docs/");
+        assert_eq!(normalize_path("///"), "/");
+    }
+
+    #[test]
+    fn dot_segments_are_dropped() {
+        assert_eq!(normalize_path("/./files/./a.txt"), "/files/a.txt");
+        assert_eq!(normalize_path("/docs/."), "/docs/");
+    }
+
+    #[test]
+    fn parent_segments_are_kept_and_the_query_is_untouched() {
+        assert_eq!(normalize_path("/files/../secret.txt"), "/files/../secret.txt");
+        assert_eq!(normalize_path("//echo//a?x=//./"), "/echo/a?x=//./");
+    }
+}
@@ -0,0 +1,10 @@
+mod auth;
+mod handlers;
+
+pub mod compression;
+pub mod config;
+pub mod file_cache;
+pub mod http;
+pub mod metrics;
+pub mod router;
+pub mod server;
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicU64, Ordering };
+
+use crate::file_cache::FileCache;
+
+/// Counters shared by every connection of a running server
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    requests_served: AtomicU64,
+    active_connections: AtomicU64,
+    file_cache: Option<Arc<FileCache>>
+}
+
+impl ServerMetrics {
+    pub(crate) fn new(file_cache: Option<Arc<FileCache>>) -> ServerMetrics {
+        ServerMetrics { file_cache, ..ServerMetrics::default() }
+    }
+
+    pub fn requests_served(&self) -> u64 {
+        self.requests_served.load(Ordering::Relaxed)
+    }
+
+    pub fn active_connections(&self) -> u64 {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+
+    /// Always 0 without --file-cache-size
+    pub fn file_cache_hits(&self) -> u64 {
+        self.file_cache.as_ref().map_or(0, |file_cache| file_cache.hits())
+    }
+
+    /// Always 0 without --file-cache-size
+    pub fn file_cache_misses(&self) -> u64 {
+        self.file_cache.as_ref().map_or(0, |file_cache| file_cache.misses())
+    }
+
+    // Returns the total including the request just served
+    pub(crate) fn record_request(&self) -> u64 {
+        self.requests_served.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub(crate) fn connection_opened(&self) {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn connection_closed(&self) {
+        self.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::auth::{ is_authorized, BASIC_AUTH_REALM };
+use crate::config::ServerConfiguration;
+use crate::handlers::debug::handle_mirror;
+use crate::handlers::files::{ handle_file, handle_static, static_file_exists };
+use crate::handlers::{ handle_echo, handle_root, handle_user_agent };
+use crate::http::types::{ HttpMethod, HttpRequest, HttpResponse, RequestTarget };
+use crate::http::uri::normalize_path;
+
+/// Produces the response for a parsed request, implemented by the built-in `Router`
+/// and by any closure, so that the server can be embedded with custom dispatch logic.
+pub trait RequestHandler: Send + Sync {
+    fn handle(&self, request: &HttpRequest) -> HttpResponse;
+}
+
+impl<F> RequestHandler for F where F: Fn(&HttpRequest) -> HttpResponse + Send + Sync {
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        self(request)
+    }
+}
+
+// Every method the built-in routes implement, as reported for OPTIONS *
+const SERVER_METHODS: &str = "GET, HEAD, POST, PUT, OPTIONS";
+
+// The built-in routes, matched on the request path alone
+enum Route<'a> {
+    Root,
+    Echo(&'a str),
+    UserAgent,
+    Files(&'a str),
+    Mirror,
+    Static(&'a str),
+    Unmatched
+}
+
+impl Route<'_> {
+    // The Allow header value for OPTIONS requests, unmatched paths are left to the fallback.
+    // The root has always answered POST and PUT with an empty 200, which is kept.
+    fn allowed_methods(&self) -> Option<&'static str> {
+        match self {
+            Route::Root => Some(SERVER_METHODS),
+            Route::Echo(_) | Route::UserAgent | Route::Static(_) => Some("GET, HEAD, OPTIONS"),
+            Route::Files(_) | Route::Mirror => Some("GET, HEAD, POST, PUT, OPTIONS"),
+            Route::Unmatched => None
+        }
+    }
+
+    // Every route that reads from the served directory is covered by --basic-auth, not only /files/
+    fn serves_directory(&self, server_configuration: &ServerConfiguration) -> bool {
+        match self {
+            Route::Files(_) | Route::Static(_) => true,
+            Route::Root => server_configuration.directory.is_some(),
+            Route::Echo(_) | Route::UserAgent | Route::Mirror | Route::Unmatched => false
+        }
+    }
+}
+
+fn handle_options(allowed_methods: &str) -> HttpResponse {
+    HttpResponse::builder(204).header("Allow", allowed_methods).build()
+}
+
+fn is_allowed(method: &HttpMethod, allowed_methods: &str) -> bool {
+    allowed_methods.split(',').any(|allowed_method| allowed_method.trim() == method.as_str())
+}
+
+pub struct Router {
+    server_configuration: ServerConfiguration,
+    fallback: Option<Box<dyn RequestHandler>>
+}
+
+impl Router {
+    pub fn new(server_configuration: ServerConfiguration) -> Router {
+        Router { server_configuration, fallback: None }
+    }
+
+    /// Handles every request that no built-in route matches, e.g. for SPA fallbacks or custom 404 pages
+    pub fn with_fallback<H: RequestHandler + 'static>(mut self, handler: H) -> Router {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    fn handle_unmatched(&self, request: &HttpRequest) -> HttpResponse {
+        match &self.fallback {
+            Some(fallback) => fallback.handle(request),
+            None => HttpResponse::not_found()
+        }
+    }
+
+    // A CORS preflight is answered before any route is consulted, the route itself only sees the actual request
+    fn handle_preflight(&self) -> HttpResponse {
+        HttpResponse::builder(204)
+            .header("Access-Control-Allow-Methods", &self.server_configuration.cors_allow_methods)
+            .header("Access-Control-Allow-Headers", &self.server_configuration.cors_allow_headers)
+            .build()
+    }
+
+    fn match_route<'a>(&self, uri: &'a str) -> Route<'a> {
+        if uri == "/" || uri.starts_with("/?") {
+            Route::Root
+        } else if let Some(text) = uri.strip_prefix("/echo/") {
+            Route::Echo(text)
+        } else if uri == "/user-agent" {
+            Route::UserAgent
+        } else if let Some(file_name) = uri.strip_prefix("/files/") {
+            Route::Files(file_name)
+        } else if self.server_configuration.enable_debug_routes && (uri == "/mirror" || uri.starts_with("/mirror?")) {
+            Route::Mirror
+        } else if self.server_configuration.directory.is_some() {
+            Route::Static(uri)
+        } else {
+            Route::Unmatched
+        }
+    }
+
+    fn route(&self, request: &HttpRequest) -> Result<HttpResponse, std::io::Error> {
+        let server_configuration = &self.server_configuration;
+        let is_preflight = request.method == HttpMethod::Options && request.headers.contains("Access-Control-Request-Method");
+        if is_preflight && server_configuration.cors_allow_origin.is_some() {
+            return Ok(self.handle_preflight());
+        }
+        let uri = match &request.target {
+            RequestTarget::Authority(authority) => {
+                let body = format!("CONNECT to {} is not supported", authority);
+                return Ok(HttpResponse::builder(501).text(&body).build());
+            }
+            RequestTarget::Asterisk if request.method == HttpMethod::Options => return Ok(handle_options(SERVER_METHODS)),
+            target => match target.path() {
+                Some(path) => path,
+                None => return Ok(self.handle_unmatched(request))
+            }
+        };
+        let normalized_uri;
+        let uri = if server_configuration.normalize_paths {
+            normalized_uri = normalize_path(uri);
+            normalized_uri.as_str()
+        } else {
+            uri
+        };
+        let route = self.match_route(uri);
+        if let Some(allowed_methods) = route.allowed_methods() {
+            if request.method == HttpMethod::Options {
+                // A missing static file is as unknown as an unmatched path, so only existing ones are described
+                let is_missing = matches!(route, Route::Static(path) if !static_file_exists(path, server_configuration));
+                return Ok(if is_missing { self.handle_unmatched(request) } else { handle_options(allowed_methods) });
+            }
+            if !is_allowed(&request.method, allowed_methods) {
+                return Ok(HttpResponse::builder(405).header("Allow", allowed_methods).build());
+            }
+        }
+        if let Some(basic_auth) = &server_configuration.basic_auth {
+            if route.serves_directory(server_configuration) && !is_authorized(request, basic_auth) {
+                return Ok(HttpResponse::unauthorized(BASIC_AUTH_REALM));
+            }
+        }
+        match route {
+            Route::Root => handle_root(request, server_configuration),
+            Route::Echo(text) => handle_echo(text),
+            Route::UserAgent => handle_user_agent(request),
+            Route::Files(file_name) => handle_file(request, file_name, server_configuration),
+            Route::Mirror => handle_mirror(request),
+            Route::Static(path) => {
+                let response = handle_static(request, path, server_configuration)?;
+                if response.status == 404 {
+                    Ok(self.handle_unmatched(request))
+                } else {
+                    Ok(response)
+                }
+            }
+            Route::Unmatched => Ok(self.handle_unmatched(request))
+        }
+    }
+}
+
+impl RequestHandler for Router {
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let mut response = self.route(request).unwrap_or_else(|error| {
+            println!("Error while handling a request: {}", error);
+            HttpResponse::internal_server_error()
+        });
+        if let Some(allow_origin) = &self.server_configuration.cors_allow_origin {
+            response.headers.set("Access-Control-Allow-Origin", allow_origin.clone());
+        }
+        response
+    }
+}
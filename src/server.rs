@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::net::{ IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs };
+use std::path::Path;
+use std::sync::{ Arc, Mutex, MutexGuard };
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{ Duration, Instant, SystemTime, UNIX_EPOCH };
+
+use crate::compression::compress_response;
+use crate::config::ServerConfiguration;
+use crate::http::connection::Connection;
+use crate::http::date::format_http_date;
+use crate::http::parser::{ parse_request, wait_for_request, RequestError };
+use crate::http::types::{ Body, HttpHeaders, HttpMethod, HttpRequest, HttpResponse };
+use crate::metrics::ServerMetrics;
+use crate::router::{ RequestHandler, Router };
+
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+const SERVER_NAME: &str = concat!("codecrafters-http-server/", env!("CARGO_PKG_VERSION"));
+
+static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn generate_request_id() -> String {
+    let started_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{:x}-{:x}", started_at.as_millis(), REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+// A client supplied X-Request-Id is kept so that a request can be traced across hops, otherwise one is generated
+fn assign_request_id(request: &mut HttpRequest) -> String {
+    let is_valid = |request_id: &str| !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id.chars().all(|c| c.is_ascii_graphic());
+    match request.headers.get("X-Request-Id") {
+        Some(request_id) if is_valid(request_id) => String::from(request_id),
+        _ => {
+            let request_id = generate_request_id();
+            request.headers.set("X-Request-Id", request_id.clone());
+            request_id
+        }
+    }
+}
+
+// Accept: text/html, application/json;q=0.9, */*;q=0.8
+// The most specific matching media range determines the quality, a type that is not matched at all gets 0
+fn accept_quality(request: &HttpRequest, media_type: &str) -> f32 {
+    let (main_type, _) = media_type.split_once('/').unwrap_or((media_type, ""));
+    let mut best_match: Option<(u8, f32)> = None;
+    let media_ranges = request.headers.get_all("Accept").iter().flat_map(|accept| accept.split(','));
+    for media_range in media_ranges {
+        let mut parts = media_range.split(';').map(|part| part.trim());
+        let range = parts.next().unwrap_or("");
+        let quality = parts.filter_map(|parameter| parameter.strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        let specificity = if range.eq_ignore_ascii_case(media_type) {
+            2
+        } else if range.strip_suffix("/*").is_some_and(|range_type| range_type.eq_ignore_ascii_case(main_type)) {
+            1
+        } else if range == "*/*" {
+            0
+        } else {
+            continue;
+        };
+        if best_match.map_or(true, |(best_specificity, _)| specificity > best_specificity) {
+            best_match = Some((specificity, quality));
+        }
+    }
+    best_match.map_or(0.0, |(_, quality)| quality)
+}
+
+// Every response carries its generation time and the server identification, unless the handler set them itself
+fn add_standard_headers(response: &mut HttpResponse) {
+    if !response.headers.contains("Date") {
+        response.headers.append(String::from("Date"), format_http_date(SystemTime::now()));
+    }
+    if !response.headers.contains("Server") {
+        response.headers.append(String::from("Server"), String::from(SERVER_NAME));
+    }
+}
+
+fn set_error_body(response: &mut HttpResponse, content_type: &str, body: Vec<u8>) {
+    response.headers.set("Content-Type", String::from(content_type));
+    response.headers.set("Content-Length", body.len().to_string());
+    response.body = body.into();
+}
+
+// Error responses are produced with an empty body. Clients preferring JSON get a JSON error,
+// otherwise a configured page replaces the body, or a minimal HTML page if the client asked for HTML.
+fn apply_error_page(request: Option<&HttpRequest>, response: &mut HttpResponse, server_configuration: &ServerConfiguration) {
+    if response.status < 400 || !response.body.is_empty() {
+        return;
+    }
+    let (json_quality, html_quality) = request.map_or((0.0, 0.0), |request| {
+        (accept_quality(request, "application/json"), accept_quality(request, "text/html"))
+    });
+    if json_quality > html_quality {
+        let body = format!("{{\"error\":{{\"status\":{},\"message\":\"{}\"}}}}", response.status, response.reason_phrase);
+        set_error_body(response, "application/json", body.into_bytes());
+    } else if let Some(error_page) = server_configuration.error_pages.get(&response.status) {
+        set_error_body(response, error_page.content_type, error_page.body.clone());
+    } else if html_quality > json_quality {
+        let title = format!("{} {}", response.status, response.reason_phrase);
+        let body = format!("<!DOCTYPE html>\n<html>\n<head><title>{}</title></head>\n<body><h1>{}</h1></body>\n</html>\n", title, title);
+        set_error_body(response, "text/html", body.into_bytes());
+    }
+}
+
+// Warms up the file cache so that the first requests for the preloaded files are already hits
+fn preload_files(server_configuration: &ServerConfiguration) {
+    if server_configuration.preload.is_empty() {
+        return;
+    }
+    match (&server_configuration.directory, &server_configuration.file_cache) {
+        (Some(directory), Some(file_cache)) => {
+            let preloaded = file_cache.preload(Path::new(directory), &server_configuration.preload);
+            println!("Preloaded {} files into the file cache", preloaded);
+        }
+        _ => println!("Ignoring --preload, it needs both --directory and --file-cache-size")
+    }
+}
+
+// An accepted TCP connection, closed for reading on shutdown while it waits for its next request
+struct TrackedConnection {
+    stream: TcpStream,
+    idle: bool
+}
+
+// The accept loop blocks in accept(), so a shutdown request also connects to the listener to wake it up.
+// A connection served without a listener has no accept loop to wake.
+// Idle persistent connections would keep their threads waiting for the next request, and Server::wait with them,
+// so they are closed as well. A connection in the middle of a request is left to complete it.
+struct ShutdownSignal {
+    requested: AtomicBool,
+    wake_address: Option<SocketAddr>,
+    connections: Mutex<HashMap<u64, TrackedConnection>>,
+    next_connection_id: AtomicU64
+}
+
+impl ShutdownSignal {
+    fn new(local_address: SocketAddr) -> ShutdownSignal {
+        // A listener bound to all interfaces is reached through the loopback interface
+        let wake_ip = match local_address.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip
+        };
+        ShutdownSignal::with_wake_address(Some(SocketAddr::new(wake_ip, local_address.port())))
+    }
+
+    fn without_listener() -> ShutdownSignal {
+        ShutdownSignal::with_wake_address(None)
+    }
+
+    fn with_wake_address(wake_address: Option<SocketAddr>) -> ShutdownSignal {
+        ShutdownSignal {
+            requested: AtomicBool::new(false),
+            wake_address,
+            connections: Mutex::new(HashMap::new()),
+            next_connection_id: AtomicU64::new(0)
+        }
+    }
+
+    // Returns None if the stream cannot be tracked, the connection is then only closed by its client or a timeout
+    fn track(&self, stream: &TcpStream) -> Option<u64> {
+        let stream = match stream.try_clone() {
+            Ok(stream) => stream,
+            Err(e) => {
+                println!("Cannot track the connection for shutdown: {}", e);
+                return None;
+            }
+        };
+        let connection_id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        self.lock_connections().insert(connection_id, TrackedConnection { stream, idle: false });
+        Some(connection_id)
+    }
+
+    fn untrack(&self, connection_id: Option<u64>) {
+        if let Some(connection_id) = connection_id {
+            self.lock_connections().remove(&connection_id);
+        }
+    }
+
+    // Returns false if shutdown was already requested, the connection then takes no further requests.
+    // Checked under the same lock that request() takes after setting the flag, so a connection
+    // either sees the request here or is closed by it.
+    fn enter_idle(&self, connection_id: Option<u64>) -> bool {
+        let mut connections = self.lock_connections();
+        if self.is_requested() {
+            return false;
+        }
+        if let Some(connection) = connection_id.and_then(|connection_id| connections.get_mut(&connection_id)) {
+            connection.idle = true;
+        }
+        true
+    }
+
+    fn leave_idle(&self, connection_id: Option<u64>) {
+        let mut connections = self.lock_connections();
+        if let Some(connection) = connection_id.and_then(|connection_id| connections.get_mut(&connection_id)) {
+            connection.idle = false;
+        }
+    }
+
+    fn lock_connections(&self) -> MutexGuard<'_, HashMap<u64, TrackedConnection>> {
+        // The map stays consistent even if a connection thread panicked while holding the lock
+        self.connections.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn close_idle_connections(&self) {
+        for connection in self.lock_connections().values().filter(|connection| connection.idle) {
+            let _ = connection.stream.shutdown(Shutdown::Read);
+        }
+    }
+
+    fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    // Returns false if shutdown was already requested before. Fails if the accept loop could not be woken up,
+    // it then only stops once the next connection is accepted.
+    fn request(&self) -> Result<bool, std::io::Error> {
+        if self.requested.swap(true, Ordering::SeqCst) {
+            return Ok(false);
+        }
+        self.close_idle_connections();
+        if let Some(wake_address) = self.wake_address {
+            TcpStream::connect(wake_address)?;
+        }
+        Ok(true)
+    }
+}
+
+// Everything a connection thread needs, shared between the accept loop and all connections
+struct ConnectionContext {
+    handler: Arc<dyn RequestHandler>,
+    server_configuration: ServerConfiguration,
+    metrics: Arc<ServerMetrics>,
+    shutdown: Arc<ShutdownSignal>
+}
+
+impl ConnectionContext {
+    fn record_request(&self) {
+        let requests_served = self.metrics.record_request();
+        let limit_reached = self.server_configuration.max_total_requests
+            .is_some_and(|max_total_requests| requests_served >= max_total_requests);
+        if !limit_reached {
+            return;
+        }
+        match self.shutdown.request() {
+            Ok(true) => println!("Served {} requests, shutting down", requests_served),
+            Ok(false) => {}
+            Err(e) => println!("Served {} requests, shutting down, but could not wake up the accept loop: {}", requests_served, e)
+        }
+    }
+}
+
+// Measured from the moment a request is parsed, so that the time a client takes to send it does not count
+struct RequestTiming {
+    request_id: String,
+    request_line: String,
+    started_at: Instant,
+    handled_at: Instant
+}
+
+impl RequestTiming {
+    fn slow_request_warning(&self, written_at: Instant, slow_request_threshold: Option<Duration>) -> Option<String> {
+        let total = written_at - self.started_at;
+        slow_request_threshold.filter(|threshold| total > *threshold)?;
+        Some(format!("[{}] WARN slow request '{}': {} ms total, {} ms handling, {} ms writing the response",
+            self.request_id,
+            self.request_line,
+            total.as_millis(),
+            (self.handled_at - self.started_at).as_millis(),
+            (written_at - self.handled_at).as_millis()))
+    }
+
+    fn log_if_slow(&self, slow_request_threshold: Option<Duration>) {
+        if let Some(warning) = self.slow_request_warning(Instant::now(), slow_request_threshold) {
+            println!("{}", warning);
+        }
+    }
+}
+
+fn has_connection_option(headers: &HttpHeaders, option: &str) -> bool {
+    headers.get_all("Connection").iter()
+        .flat_map(|connection| connection.split(','))
+        .any(|connection_option| connection_option.trim().eq_ignore_ascii_case(option))
+}
+
+// HTTP/1.1 connections stay open unless the client asks to close, HTTP/1.0 ones only if it asks for keep-alive
+fn is_persistent(request: &HttpRequest) -> bool {
+    match request.http_version.as_str() {
+        "HTTP/1.1" => !has_connection_option(&request.headers, "close"),
+        "HTTP/1.0" => has_connection_option(&request.headers, "keep-alive"),
+        _ => false
+    }
+}
+
+// On a persistent connection the client can only find the end of a body by its length or by chunked framing
+fn ensure_content_length(response: &mut HttpResponse) {
+    let may_have_body = response.status >= 200 && response.status != 204 && response.status != 304;
+    if may_have_body && !response.headers.contains("Content-Length") && !response.headers.contains("Transfer-Encoding") {
+        response.headers.append(String::from("Content-Length"), response.body.len().to_string());
+    }
+}
+
+// Returns whether the connection stays open for another request
+fn handle_request<C: Connection>(reader: &mut BufReader<C>, context: &ConnectionContext) -> Result<bool, std::io::Error> {
+    let server_configuration = &context.server_configuration;
+    let mut timing: Option<RequestTiming> = None;
+    let mut keep_alive = false;
+    let mut response = match parse_request(reader, server_configuration.max_request_memory) {
+        Ok(mut request) => {
+            let started_at = Instant::now();
+            let request_id = assign_request_id(&mut request);
+            println!("[{}] {} {} {}", request_id, request.method.as_str(), request.uri, request.http_version);
+            let mut response = context.handler.handle(&request);
+            // A buffered response body counts against the same budget as the request that produced it,
+            // a streamed one is never held in memory as a whole
+            let used_bytes = request.allocated_bytes() + response.body.len();
+            if server_configuration.max_request_memory.is_some_and(|max_bytes| used_bytes > max_bytes) {
+                println!("[{}] Rejecting request: {}", request_id, RequestError::MemoryLimitExceeded(used_bytes));
+                response = HttpResponse::payload_too_large();
+            }
+            compress_response(&request, &mut response, server_configuration.compression_level)?;
+            if !response.headers.contains("X-Request-Id") {
+                response.headers.append(String::from("X-Request-Id"), request_id.clone());
+            }
+            apply_error_page(Some(&request), &mut response, server_configuration);
+            keep_alive = is_persistent(&request) && !has_connection_option(&response.headers, "close");
+            // The response is never of a higher version than the client understands
+            if request.http_version == "HTTP/1.0" {
+                response.http_version = String::from("HTTP/1.0");
+            }
+            ensure_content_length(&mut response);
+            // HEAD gets the same headers as GET, including Content-Length, but never a body
+            if request.method == HttpMethod::Head {
+                response.body = Body::default();
+                response.streamed_body = None;
+            }
+            timing = Some(RequestTiming {
+                request_id,
+                request_line: format!("{} {}", request.method.as_str(), request.uri),
+                started_at,
+                handled_at: Instant::now()
+            });
+            response
+        }
+        Err(error) => match RequestError::from_io_error(&error) {
+            Some(RequestError::NoRequest) => return Ok(false),
+            Some(request_error) => {
+                println!("Rejecting request: {}", request_error);
+                let mut response = request_error.to_response();
+                apply_error_page(None, &mut response, server_configuration);
+                ensure_content_length(&mut response);
+                response
+            }
+            None => return Err(error)
+        }
+    };
+    // Counted before writing, so that the request reaching --max-total-requests already closes its connection
+    context.record_request();
+    let keep_alive = keep_alive && !context.shutdown.is_requested();
+    if !keep_alive {
+        response.headers.set("Connection", String::from("close"));
+    } else if response.http_version == "HTTP/1.0" {
+        response.headers.set("Connection", String::from("keep-alive"));
+    }
+    add_standard_headers(&mut response);
+    response.write_to(reader.get_mut())?;
+    reader.get_mut().flush()?;
+    if let Some(timing) = timing {
+        timing.log_if_slow(server_configuration.slow_request_threshold);
+    }
+    println!("Handled request correctly");
+    Ok(keep_alive)
+}
+
+// Tracked connections can be closed by a shutdown request while they wait for their next request
+fn serve_connection<C: Connection>(connection: C, context: &ConnectionContext, connection_id: Option<u64>) -> C {
+    println!("accepted new connection");
+    context.metrics.connection_opened();
+    let mut reader = BufReader::new(connection);
+    loop {
+        if !context.shutdown.enter_idle(connection_id) {
+            break;
+        }
+        let has_request = wait_for_request(&mut reader);
+        context.shutdown.leave_idle(connection_id);
+        match has_request {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => {
+                println!("Error while waiting for a request: {}", e);
+                break;
+            }
+        }
+        match handle_request(&mut reader, context) {
+            Ok(true) => continue,
+            Ok(false) => break,
+            Err(e) => {
+                println!("Error while handling a request: {}", e);
+                break;
+            }
+        }
+    }
+    context.metrics.connection_closed();
+    reader.into_inner()
+}
+
+/// Serves the requests read from a single connection until it ends, without binding a listener,
+/// e.g. to exercise a handler over a `MemoryConnection`. Returns the connection with everything written to it.
+pub fn serve<C: Connection, H: RequestHandler + 'static>(connection: C, handler: H, server_configuration: ServerConfiguration) -> C {
+    let context = ConnectionContext {
+        handler: Arc::new(handler),
+        server_configuration,
+        metrics: Arc::new(ServerMetrics::default()),
+        shutdown: Arc::new(ShutdownSignal::without_listener())
+    };
+    serve_connection(connection, &context, None)
+}
+
+// Once shutdown is requested no new connections are accepted, but the ones already accepted are completed.
+// The connection that woke the loop up, or any other accepted after the request, is dropped unanswered.
+fn run_accept_loop(listener: TcpListener, context: Arc<ConnectionContext>) {
+    let mut connections: Vec<JoinHandle<()>> = Vec::new();
+    while !context.shutdown.is_requested() {
+        match listener.accept() {
+            Ok(_) if context.shutdown.is_requested() => break,
+            Ok((stream, _)) => {
+                let context = Arc::clone(&context);
+                let connection_id = context.shutdown.track(&stream);
+                connections.retain(|connection| !connection.is_finished());
+                connections.push(thread::spawn(move || {
+                    serve_connection(stream, &context, connection_id);
+                    context.shutdown.untrack(connection_id);
+                }));
+            }
+            Err(e) => {
+                println!("error: {}", e);
+            }
+        }
+    }
+    drop(listener);
+    for connection in connections {
+        if connection.join().is_err() {
+            println!("Connection terminated with a panic");
+        }
+    }
+}
+
+pub struct Server {
+    local_address: SocketAddr,
+    metrics: Arc<ServerMetrics>,
+    shutdown: Arc<ShutdownSignal>,
+    accept_loop: JoinHandle<()>
+}
+
+impl Server {
+    /// Serves the built-in routes configured by `server_configuration`
+    pub fn start<A: ToSocketAddrs>(address: A, server_configuration: ServerConfiguration) -> Result<Server, std::io::Error> {
+        preload_files(&server_configuration);
+        let router = Router::new(server_configuration.clone());
+        Server::launch(address, Arc::new(router), server_configuration)
+    }
+
+    /// Dispatches every request to `handler` instead of the built-in routes
+    pub fn start_with_handler<A: ToSocketAddrs, H: RequestHandler + 'static>(address: A, handler: H) -> Result<Server, std::io::Error> {
+        Server::launch(address, Arc::new(handler), ServerConfiguration::default())
+    }
+
+    fn launch<A: ToSocketAddrs>(address: A, handler: Arc<dyn RequestHandler>, server_configuration: ServerConfiguration) -> Result<Server, std::io::Error> {
+        let listener = TcpListener::bind(address)?;
+        let local_address = listener.local_addr()?;
+        let metrics = Arc::new(ServerMetrics::new(server_configuration.file_cache.clone()));
+        let shutdown = Arc::new(ShutdownSignal::new(local_address));
+        let context = Arc::new(ConnectionContext {
+            handler,
+            server_configuration,
+            metrics: Arc::clone(&metrics),
+            shutdown: Arc::clone(&shutdown)
+        });
+        let accept_loop = thread::spawn(move || run_accept_loop(listener, context));
+        Ok(Server { local_address, metrics, shutdown, accept_loop })
+    }
+
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    pub fn metrics(&self) -> &ServerMetrics {
+        &self.metrics
+    }
+
+    /// Stops accepting new connections, connections already accepted are still served.
+    /// Fails if the blocked accept loop could not be woken up, it then stops with the next accepted connection.
+    pub fn shutdown(&self) -> Result<(), std::io::Error> {
+        self.shutdown.request().map(|_| ())
+    }
+
+    /// Blocks until the accept loop exits and all accepted connections are served
+    pub fn wait(self) {
+        if self.accept_loop.join().is_err() {
+            println!("Accept loop terminated with a panic");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(handling: Duration) -> RequestTiming {
+        let started_at = Instant::now();
+        RequestTiming {
+            request_id: String::from("abc"),
+            request_line: String::from("GET /files/large.bin"),
+            started_at,
+            handled_at: started_at + handling
+        }
+    }
+
+    #[test]
+    fn slow_request_is_reported_with_its_phases() {
+        let timing = timing(Duration::from_millis(120));
+        let written_at = timing.started_at + Duration::from_millis(150);
+
+        let warning = timing.slow_request_warning(written_at, Some(Duration::from_millis(100)));
+
+        assert_eq!(warning.as_deref(), Some("[abc] WARN slow request 'GET /files/large.bin': 150 ms total, 120 ms handling, 30 ms writing the response"));
+    }
+
+    #[test]
+    fn fast_request_is_not_reported() {
+        let timing = timing(Duration::from_millis(5));
+        let written_at = timing.started_at + Duration::from_millis(10);
+
+        assert_eq!(timing.slow_request_warning(written_at, Some(Duration::from_millis(100))), None);
+        assert_eq!(timing.slow_request_warning(written_at + Duration::from_secs(60), None), None);
+    }
+}
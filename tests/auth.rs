@@ -0,0 +1,79 @@
+mod common;
+
+use http_server_starter_rust::config::{ BasicAuth, ServerConfiguration };
+use http_server_starter_rust::server::Server;
+
+use common::{ get, start, TestDirectory };
+
+// user:secret and user:wrong
+const VALID_CREDENTIALS: &str = "Basic dXNlcjpzZWNyZXQ=";
+const WRONG_CREDENTIALS: &str = "Basic dXNlcjp3cm9uZw==";
+
+fn start_protected(directory: &TestDirectory) -> Server {
+    start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        basic_auth: Some(BasicAuth { username: String::from("user"), password: String::from("secret") }),
+        ..ServerConfiguration::default()
+    })
+}
+
+#[test]
+fn missing_credentials_are_rejected() {
+    let directory = TestDirectory::new();
+    directory.write("secret.txt", b"top secret");
+    let server = start_protected(&directory);
+
+    let response = get(server.local_address(), "/files/secret.txt", &[]);
+
+    assert_eq!(response.status, 401);
+    assert_eq!(response.header("WWW-Authenticate"), Some("Basic realm=\"files\""));
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn wrong_credentials_are_rejected() {
+    let directory = TestDirectory::new();
+    directory.write("secret.txt", b"top secret");
+    let server = start_protected(&directory);
+
+    let response = get(server.local_address(), "/files/secret.txt", &[("Authorization", WRONG_CREDENTIALS)]);
+
+    assert_eq!(response.status, 401);
+}
+
+#[test]
+fn correct_credentials_serve_the_file() {
+    let directory = TestDirectory::new();
+    directory.write("secret.txt", b"top secret");
+    let server = start_protected(&directory);
+
+    let response = get(server.local_address(), "/files/secret.txt", &[("Authorization", VALID_CREDENTIALS)]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"top secret");
+}
+
+#[test]
+fn static_files_outside_files_route_are_protected_too() {
+    let directory = TestDirectory::new();
+    directory.write("secret.txt", b"top secret");
+    directory.write("index.html", b"<h1>index</h1>");
+    let server = start_protected(&directory);
+
+    assert_eq!(get(server.local_address(), "/secret.txt", &[]).status, 401);
+    assert_eq!(get(server.local_address(), "/", &[]).status, 401);
+    let response = get(server.local_address(), "/secret.txt", &[("Authorization", VALID_CREDENTIALS)]);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"top secret");
+}
+
+#[test]
+fn routes_not_reading_the_directory_stay_open() {
+    let directory = TestDirectory::new();
+    let server = start_protected(&directory);
+
+    let response = get(server.local_address(), "/echo/hello", &[]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hello");
+}
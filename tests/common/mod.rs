@@ -0,0 +1,192 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::{ Read, Write };
+use std::net::{ SocketAddr, TcpStream };
+use std::path::{ Path, PathBuf };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::time::Duration;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+static DIRECTORY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// A scratch directory under the system temp directory, removed again when dropped
+pub struct TestDirectory {
+    path: PathBuf
+}
+
+impl TestDirectory {
+    pub fn new() -> TestDirectory {
+        let name = format!("http-server-test-{}-{}", std::process::id(), DIRECTORY_COUNTER.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        fs::create_dir_all(&path).expect("cannot create the test directory");
+        TestDirectory { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn path_string(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    pub fn write(&self, relative_path: &str, contents: &[u8]) -> PathBuf {
+        let file_path = self.path.join(relative_path);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).expect("cannot create the parent directory");
+        }
+        fs::write(&file_path, contents).expect("cannot write the test file");
+        file_path
+    }
+}
+
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+pub fn start(server_configuration: ServerConfiguration) -> Server {
+    Server::start("127.0.0.1:0", server_configuration).expect("cannot start the server")
+}
+
+pub fn start_serving(directory: &TestDirectory) -> Server {
+    start(ServerConfiguration { directory: Some(directory.path_string()), ..ServerConfiguration::default() })
+}
+
+pub fn connect(address: SocketAddr) -> TcpStream {
+    let stream = TcpStream::connect(address).expect("cannot connect to the server");
+    stream.set_read_timeout(Some(Duration::from_secs(5))).expect("cannot set the read timeout");
+    stream
+}
+
+// Writes the raw request and reads until the server closes the connection
+pub fn send_raw(address: SocketAddr, request: &[u8]) -> Vec<u8> {
+    let mut stream = connect(address);
+    stream.write_all(request).expect("cannot send the request");
+    let mut received: Vec<u8> = Vec::new();
+    stream.read_to_end(&mut received).expect("cannot read the response");
+    received
+}
+
+// Reads a single response from a connection that stays open
+pub fn read_response(stream: &mut TcpStream) -> Response {
+    let mut received: Vec<u8> = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        if let Some(response) = Response::parse(&received) {
+            return response;
+        }
+        let read = stream.read(&mut buffer).expect("cannot read the response");
+        assert!(read > 0, "the connection was closed before the response was complete");
+        received.extend_from_slice(&buffer[..read]);
+    }
+}
+
+pub fn send(address: SocketAddr, request: &str) -> Response {
+    Response::parse(&send_raw(address, request.as_bytes())).expect("incomplete response")
+}
+
+// GET with Connection: close, so that the response is complete once the server closes the connection
+pub fn get(address: SocketAddr, path: &str, headers: &[(&str, &str)]) -> Response {
+    request(address, "GET", path, headers, b"")
+}
+
+pub fn request(address: SocketAddr, method: &str, path: &str, headers: &[(&str, &str)], body: &[u8]) -> Response {
+    let mut raw = format!("{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n", method, path);
+    for (name, value) in headers {
+        raw.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if !body.is_empty() {
+        raw.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    raw.push_str("\r\n");
+    let mut raw = raw.into_bytes();
+    raw.extend_from_slice(body);
+    Response::parse_with(&send_raw(address, &raw), method != "HEAD").expect("incomplete response")
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status_line: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    // Whatever followed this response on the connection, e.g. a pipelined response
+    pub rest: Vec<u8>
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+// 5\r\nhello\r\n0\r\n\r\n
+fn decode_chunked(mut data: &[u8]) -> Option<(Vec<u8>, &[u8])> {
+    let mut body: Vec<u8> = Vec::new();
+    loop {
+        let line_end = find(data, b"\r\n")?;
+        let size_line = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_line.split(';').next()?.trim(), 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some((body, data.get(2..)?));
+        }
+        body.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+impl Response {
+    // Parses the first response in `data`, anything after it is left in `rest`
+    pub fn parse(data: &[u8]) -> Option<Response> {
+        Response::parse_with(data, true)
+    }
+
+    // Responses to HEAD carry the Content-Length of the GET response, but no body
+    pub fn parse_with(data: &[u8], has_body: bool) -> Option<Response> {
+        let head_end = find(data, b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+        let mut lines = head.split("\r\n");
+        let status_line = String::from(lines.next()?);
+        let status = status_line.split(' ').nth(1)?.parse::<u16>().ok()?;
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (String::from(name.trim()), String::from(value.trim())))
+            .collect();
+        let mut response = Response { status_line, status, headers, body: Vec::new(), rest: Vec::new() };
+        let payload = &data[head_end + 4..];
+        let (body, rest) = if !has_body || status == 304 || status == 204 {
+            (Vec::new(), payload)
+        } else if response.header("Transfer-Encoding").is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked")) {
+            decode_chunked(payload)?
+        } else if let Some(content_length) = response.header("Content-Length") {
+            let content_length = content_length.parse::<usize>().ok()?;
+            (payload.get(..content_length)?.to_vec(), &payload[content_length..])
+        } else {
+            (payload.to_vec(), &payload[payload.len()..])
+        };
+        response.body = body;
+        response.rest = rest.to_vec();
+        Some(response)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn header_all(&self, name: &str) -> Vec<&str> {
+        self.headers.iter()
+            .filter(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use http_server_starter_rust::config::ServerConfiguration;
+
+use common::{ get, start, start_serving, TestDirectory };
+
+fn gunzip(data: &[u8]) -> Vec<u8> {
+    let mut decoded: Vec<u8> = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut decoded).unwrap();
+    decoded
+}
+
+fn unbrotli(data: &[u8]) -> Vec<u8> {
+    let mut decoded: Vec<u8> = Vec::new();
+    brotli::Decompressor::new(data, 4096).read_to_end(&mut decoded).unwrap();
+    decoded
+}
+
+#[test]
+fn user_agent_is_gzip_encoded_when_accepted() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/user-agent", &[("User-Agent", "test-agent/1.0"), ("Accept-Encoding", "gzip")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.header("Vary"), Some("Accept-Encoding"));
+    assert_eq!(response.header("Content-Length"), Some(response.body.len().to_string().as_str()));
+    assert_eq!(gunzip(&response.body), b"test-agent/1.0");
+}
+
+#[test]
+fn brotli_is_preferred_when_accepted() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/echo/brotli-encoded", &[("Accept-Encoding", "gzip, br")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Encoding"), Some("br"));
+    assert_eq!(unbrotli(&response.body), b"brotli-encoded");
+}
+
+#[test]
+fn no_transform_request_is_sent_as_is() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/echo/untouched", &[("Accept-Encoding", "gzip, br"), ("Cache-Control", "no-cache, no-transform")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Encoding"), None);
+    assert_eq!(response.body, b"untouched");
+}
+
+#[test]
+fn identity_is_sent_without_accept_encoding() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/user-agent", &[("User-Agent", "test-agent/1.0")]);
+
+    assert_eq!(response.header("Content-Encoding"), None);
+    assert_eq!(response.body, b"test-agent/1.0");
+}
+
+#[test]
+fn encoded_file_gets_a_weak_etag_that_still_revalidates() {
+    let directory = TestDirectory::new();
+    directory.write("notes.txt", "some notes worth compressing ".repeat(20).as_bytes());
+    let server = start_serving(&directory);
+
+    let identity = get(server.local_address(), "/notes.txt", &[]);
+    let encoded = get(server.local_address(), "/notes.txt", &[("Accept-Encoding", "gzip")]);
+
+    let identity_etag = identity.header("ETag").unwrap();
+    let encoded_etag = encoded.header("ETag").unwrap();
+    assert!(identity_etag.starts_with('"'));
+    assert_eq!(encoded_etag, format!("W/{}", identity_etag));
+    let revalidated = get(server.local_address(), "/notes.txt", &[("Accept-Encoding", "gzip"), ("If-None-Match", encoded_etag)]);
+    assert_eq!(revalidated.status, 304);
+}
+
+#[test]
+fn not_modified_carries_the_validators_of_the_matching_200() {
+    let directory = TestDirectory::new();
+    directory.write("notes.txt", "some notes worth compressing ".repeat(20).as_bytes());
+    let server = start_serving(&directory);
+    let encoded = get(server.local_address(), "/notes.txt", &[("Accept-Encoding", "gzip")]);
+    let identity = get(server.local_address(), "/notes.txt", &[]);
+
+    let encoded_etag = encoded.header("ETag").unwrap();
+    let encoded_revalidated = get(server.local_address(), "/notes.txt", &[("Accept-Encoding", "gzip"), ("If-None-Match", encoded_etag)]);
+    let identity_revalidated = get(server.local_address(), "/notes.txt", &[("If-None-Match", encoded_etag)]);
+
+    assert_eq!(encoded_revalidated.status, 304);
+    assert_eq!(encoded_revalidated.header("ETag"), Some(encoded_etag));
+    assert_eq!(encoded_revalidated.header("Vary"), Some("Accept-Encoding"));
+    assert_eq!(identity_revalidated.status, 304);
+    assert_eq!(identity_revalidated.header("ETag"), identity.header("ETag"));
+    assert_eq!(identity_revalidated.header("Vary"), Some("Accept-Encoding"));
+}
+
+#[test]
+fn large_file_is_streamed_gzip_encoded() {
+    let directory = TestDirectory::new();
+    // Well above the streaming threshold, and not trivially compressible
+    let contents: Vec<u8> = (0..3 * 1024 * 1024u32).map(|idx| (idx.wrapping_mul(2654435761) >> 24) as u8 % 16 + b'a').collect();
+    directory.write("large.txt", &contents);
+    let server = start_serving(&directory);
+
+    let response = get(server.local_address(), "/large.txt", &[("Accept-Encoding", "gzip")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.header("Transfer-Encoding"), Some("chunked"));
+    assert_eq!(response.header("Content-Length"), None);
+    assert!(gunzip(&response.body) == contents, "the decompressed body differs from the file");
+}
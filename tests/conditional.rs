@@ -0,0 +1,106 @@
+mod common;
+
+use common::{ get, request, start_serving, TestDirectory };
+
+#[test]
+fn etag_is_stable_while_the_file_is_unchanged() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+
+    let first = get(server.local_address(), "/files/data.bin", &[]);
+    let second = get(server.local_address(), "/files/data.bin", &[]);
+
+    let etag = first.header("ETag").expect("no ETag");
+    assert!(etag.starts_with('"') && etag.ends_with('"'), "not a strong ETag: {}", etag);
+    assert_eq!(second.header("ETag"), Some(etag));
+}
+
+#[test]
+fn etag_changes_when_the_file_changes() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+    let before = get(server.local_address(), "/files/data.bin", &[]);
+
+    directory.write("data.bin", b"other, longer bytes");
+    let after = get(server.local_address(), "/files/data.bin", &[]);
+
+    assert_ne!(before.header("ETag"), after.header("ETag"));
+}
+
+#[test]
+fn matching_if_none_match_gives_304_without_body() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+    let etag = String::from(get(server.local_address(), "/files/data.bin", &[]).header("ETag").unwrap());
+
+    let response = get(server.local_address(), "/files/data.bin", &[("If-None-Match", &format!("\"other\", {}", etag))]);
+
+    assert_eq!(response.status, 304);
+    assert_eq!(response.header("ETag"), Some(etag.as_str()));
+    assert!(response.body.is_empty());
+    let wildcard = get(server.local_address(), "/files/data.bin", &[("If-None-Match", "*")]);
+    assert_eq!(wildcard.status, 304);
+}
+
+#[test]
+fn stale_if_none_match_gives_the_full_response() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+
+    let response = get(server.local_address(), "/files/data.bin", &[("If-None-Match", "\"stale\"")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"some bytes");
+}
+
+#[test]
+fn if_modified_since_gives_304_until_the_file_changes() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+    let last_modified = String::from(get(server.local_address(), "/files/data.bin", &[]).header("Last-Modified").expect("no Last-Modified"));
+
+    let not_modified = get(server.local_address(), "/files/data.bin", &[("If-Modified-Since", &last_modified)]);
+    assert_eq!(not_modified.status, 304);
+    assert_eq!(not_modified.header("Last-Modified"), Some(last_modified.as_str()));
+
+    let modified = get(server.local_address(), "/files/data.bin", &[("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT")]);
+    assert_eq!(modified.status, 200);
+
+    let unparsable = get(server.local_address(), "/files/data.bin", &[("If-Modified-Since", "yesterday")]);
+    assert_eq!(unparsable.status, 200);
+}
+
+#[test]
+fn if_none_match_takes_precedence_over_if_modified_since() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+    let last_modified = String::from(get(server.local_address(), "/files/data.bin", &[]).header("Last-Modified").unwrap());
+
+    let response = get(server.local_address(), "/files/data.bin", &[("If-None-Match", "\"stale\""), ("If-Modified-Since", &last_modified)]);
+
+    assert_eq!(response.status, 200);
+}
+
+#[test]
+fn head_carries_the_validators_of_get_without_a_body() {
+    let directory = TestDirectory::new();
+    directory.write("data.bin", b"some bytes");
+    let server = start_serving(&directory);
+
+    let get_response = get(server.local_address(), "/files/data.bin", &[]);
+    let head_response = request(server.local_address(), "HEAD", "/files/data.bin", &[], b"");
+
+    assert_eq!(head_response.status, 200);
+    assert!(head_response.header("ETag").is_some());
+    assert_eq!(head_response.header("ETag"), get_response.header("ETag"));
+    assert_eq!(head_response.header("Last-Modified"), get_response.header("Last-Modified"));
+    assert_eq!(head_response.header("Content-Length"), Some("10"));
+    assert!(head_response.body.is_empty());
+    assert!(head_response.rest.is_empty(), "HEAD response had a body");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::io::{ Read, Write };
+
+use http_server_starter_rust::config::ServerConfiguration;
+
+use common::{ connect, send, send_raw, start, start_serving, Response, TestDirectory };
+
+#[test]
+fn http_1_0_requests_are_answered_as_http_1_0_and_closed() {
+    let server = start(ServerConfiguration::default());
+    let mut stream = connect(server.local_address());
+
+    stream.write_all(b"GET /echo/old HTTP/1.0\r\n\r\n").unwrap();
+    let mut received: Vec<u8> = Vec::new();
+    // Only returns once the server closed the connection
+    stream.read_to_end(&mut received).unwrap();
+
+    let response = Response::parse(&received).unwrap();
+    assert_eq!(response.status_line, "HTTP/1.0 200 OK");
+    assert_eq!(response.header("Connection"), Some("close"));
+    assert_eq!(response.body, b"old");
+    assert!(response.rest.is_empty());
+}
+
+#[test]
+fn http_1_0_keep_alive_is_honoured() {
+    let server = start(ServerConfiguration::default());
+
+    let received = send_raw(server.local_address(), b"GET /echo/one HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /echo/two HTTP/1.0\r\n\r\n");
+
+    let first = Response::parse(&received).unwrap();
+    assert_eq!(first.status_line, "HTTP/1.0 200 OK");
+    assert_eq!(first.header("Connection"), Some("keep-alive"));
+    assert_eq!(first.body, b"one");
+    let second = Response::parse(&first.rest).unwrap();
+    assert_eq!(second.body, b"two");
+    assert_eq!(second.header("Connection"), Some("close"));
+}
+
+#[test]
+fn chunked_request_body_is_not_read_as_the_next_request() {
+    let server = start(ServerConfiguration::default());
+    let smuggled = "GET /echo/smuggled HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let request = format!("POST /echo/first HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n", smuggled.len(), smuggled);
+
+    let received = send_raw(server.local_address(), request.as_bytes());
+
+    let response = Response::parse(&received).unwrap();
+    assert_eq!(response.status, 501);
+    assert_eq!(response.header("Connection"), Some("close"));
+    assert!(response.rest.is_empty(), "a second response was sent: {}", String::from_utf8_lossy(&response.rest));
+}
+
+fn count_occurrences(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack.windows(needle.len()).filter(|window| *window == needle).count()
+}
+
+#[test]
+fn exactly_one_continue_precedes_the_body() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+    let mut stream = connect(server.local_address());
+
+    stream.write_all(b"POST /files/upload.txt HTTP/1.1\r\nContent-Length: 5\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n").unwrap();
+    let mut interim = [0; 25];
+    stream.read_exact(&mut interim).unwrap();
+    assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+    stream.write_all(b"hello").unwrap();
+    let mut received: Vec<u8> = Vec::new();
+    stream.read_to_end(&mut received).unwrap();
+
+    assert_eq!(count_occurrences(&received, b"100 Continue"), 0);
+    assert_eq!(Response::parse(&received).unwrap().status, 201);
+    assert_eq!(std::fs::read(directory.path().join("upload.txt")).unwrap(), b"hello");
+}
+
+#[test]
+fn http_1_0_clients_get_no_continue() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+
+    let received = send_raw(server.local_address(), b"POST /files/upload.txt HTTP/1.0\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\nhello");
+
+    assert_eq!(count_occurrences(&received, b"100 Continue"), 0);
+    assert_eq!(Response::parse(&received).unwrap().status, 201);
+}
+
+#[test]
+fn connect_is_rejected_with_501() {
+    let server = start(ServerConfiguration::default());
+
+    let response = send(server.local_address(), "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\nConnection: close\r\n\r\n");
+
+    assert_eq!(response.status, 501);
+    assert_eq!(response.text(), "CONNECT to example.com:443 is not supported");
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, request, start };
+
+fn start_with_cors() -> Server {
+    start(ServerConfiguration {
+        cors_allow_origin: Some(String::from("https://app.example.com")),
+        cors_allow_methods: String::from("GET, POST"),
+        cors_allow_headers: String::from("Content-Type, X-Token"),
+        ..ServerConfiguration::default()
+    })
+}
+
+#[test]
+fn preflight_is_answered_with_the_configured_headers() {
+    let server = start_with_cors();
+
+    let response = request(server.local_address(), "OPTIONS", "/echo/abc", &[
+        ("Origin", "https://app.example.com"),
+        ("Access-Control-Request-Method", "POST")
+    ], b"");
+
+    assert_eq!(response.status, 204);
+    assert_eq!(response.header("Access-Control-Allow-Origin"), Some("https://app.example.com"));
+    assert_eq!(response.header("Access-Control-Allow-Methods"), Some("GET, POST"));
+    assert_eq!(response.header("Access-Control-Allow-Headers"), Some("Content-Type, X-Token"));
+}
+
+#[test]
+fn plain_get_carries_the_allowed_origin() {
+    let server = start_with_cors();
+
+    let response = get(server.local_address(), "/echo/abc", &[("Origin", "https://app.example.com")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "abc");
+    assert_eq!(response.header("Access-Control-Allow-Origin"), Some("https://app.example.com"));
+    assert_eq!(response.header("Access-Control-Allow-Methods"), None);
+}
+
+#[test]
+fn no_cors_headers_unless_configured() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/echo/abc", &[("Origin", "https://app.example.com")]);
+
+    assert_eq!(response.header("Access-Control-Allow-Origin"), None);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, request, start };
+
+fn start_with_debug_routes() -> Server {
+    start(ServerConfiguration { enable_debug_routes: true, ..ServerConfiguration::default() })
+}
+
+#[test]
+fn mirror_is_disabled_by_default() {
+    let server = start(ServerConfiguration::default());
+
+    assert_eq!(get(server.local_address(), "/mirror", &[]).status, 404);
+}
+
+#[test]
+fn mirror_reflects_the_request_as_json() {
+    let server = start_with_debug_routes();
+
+    let response = request(server.local_address(), "POST", "/mirror?a=1&b=x+y", &[("X-Custom", "value")], b"ignored");
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    let body = response.text();
+    assert!(body.starts_with("{\"method\":\"POST\",\"path\":\"/mirror\",\"query\":[[\"a\",\"1\"],[\"b\",\"x y\"]],\"headers\":["), "{}", body);
+    assert!(body.contains("[\"X-Custom\",\"value\"]"), "{}", body);
+}
+
+#[test]
+fn mirror_overrides_status_and_headers() {
+    let server = start_with_debug_routes();
+
+    let response = get(server.local_address(), "/mirror?status=418&header=X-Test:1", &[]);
+
+    assert_eq!(response.status, 418);
+    assert_eq!(response.header("X-Test"), Some("1"));
+}
+
+#[test]
+fn mirror_rejects_header_injection() {
+    let server = start_with_debug_routes();
+
+    let response = get(server.local_address(), "/mirror?header=X-Test:1%0d%0aSet-Cookie:%20a=b", &[]);
+
+    assert_eq!(response.status, 400);
+    assert_eq!(response.header("Set-Cookie"), None);
+    assert_eq!(response.header("X-Test"), None);
+}
+
+#[test]
+fn mirror_rejects_framing_headers() {
+    let server = start_with_debug_routes();
+
+    for header in ["Content-Length:0", "Transfer-Encoding:chunked", "Connection:keep-alive"] {
+        let response = get(server.local_address(), &format!("/mirror?header={}", header), &[]);
+        assert_eq!(response.status, 400, "{} was accepted", header);
+    }
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use http_server_starter_rust::http::types::{ HttpRequest, HttpResponse };
+use http_server_starter_rust::server::Server;
+
+use common::get;
+
+#[test]
+fn closure_handler_answers_every_request() {
+    let handler = |request: &HttpRequest| {
+        HttpResponse::builder(418).text(&format!("no coffee at {}", request.target.path().unwrap_or(""))).build()
+    };
+    let server = Server::start_with_handler("127.0.0.1:0", handler).unwrap();
+
+    let response = get(server.local_address(), "/brew", &[]);
+
+    assert_eq!(response.status, 418);
+    assert_eq!(response.text(), "no coffee at /brew");
+}
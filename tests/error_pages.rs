@@ -0,0 +1,78 @@
+mod common;
+
+use std::collections::HashMap;
+
+use http_server_starter_rust::config::{ ErrorPage, ServerConfiguration };
+
+use common::{ get, start, TestDirectory };
+
+#[test]
+fn json_clients_get_a_json_error() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/missing", &[("Accept", "application/json")]);
+
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    assert_eq!(response.text(), "{\"error\":{\"status\":404,\"message\":\"Not Found\"}}");
+}
+
+#[test]
+fn html_clients_get_an_html_error() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/missing", &[("Accept", "text/html,application/xhtml+xml,*/*;q=0.8")]);
+
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Content-Type"), Some("text/html"));
+    assert!(response.text().contains("<title>404 Not Found</title>"));
+}
+
+#[test]
+fn the_more_specific_media_range_decides() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/missing", &[("Accept", "text/*;q=0.5, application/json;q=0.9")]);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+
+    let response = get(server.local_address(), "/missing", &[("Accept", "application/json;q=0.2, text/html")]);
+    assert_eq!(response.header("Content-Type"), Some("text/html"));
+}
+
+#[test]
+fn clients_without_a_preference_get_an_empty_error() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/missing", &[]);
+
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Content-Length"), Some("0"));
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn configured_page_replaces_the_empty_404_body() {
+    let mut error_pages = HashMap::new();
+    error_pages.insert(404, ErrorPage { content_type: "text/html", body: b"<h1>Nothing here</h1>".to_vec() });
+    let server = start(ServerConfiguration { error_pages, ..ServerConfiguration::default() });
+
+    let response = get(server.local_address(), "/missing", &[]);
+
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Content-Type"), Some("text/html"));
+    assert_eq!(response.header("Content-Length"), Some("21"));
+    assert_eq!(response.body, b"<h1>Nothing here</h1>");
+}
+
+#[test]
+fn configured_page_is_loaded_from_the_command_line() {
+    let directory = TestDirectory::new();
+    let page = directory.write("404.html", b"<h1>Lost?</h1>");
+    let args: Vec<String> = ["http-server", "--error-page", &format!("404={}", page.display())].iter().map(|arg| String::from(*arg)).collect();
+    let server = start(ServerConfiguration::from_args(&args).unwrap());
+
+    let response = get(server.local_address(), "/missing", &[]);
+
+    assert_eq!(response.body, b"<h1>Lost?</h1>");
+    assert_eq!(get(server.local_address(), "/echo/found", &[]).body, b"found");
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::http::types::{ HttpRequest, HttpResponse };
+use http_server_starter_rust::router::Router;
+use http_server_starter_rust::server::Server;
+
+use common::get;
+
+fn start_with_fallback() -> Server {
+    let router = Router::new(ServerConfiguration::default())
+        .with_fallback(|request: &HttpRequest| {
+            HttpResponse::builder(200).text(&format!("fallback for {}", request.target.path().unwrap_or(""))).build()
+        });
+    Server::start_with_handler("127.0.0.1:0", router).expect("cannot start the server")
+}
+
+#[test]
+fn fallback_answers_unknown_paths() {
+    let server = start_with_fallback();
+
+    let response = get(server.local_address(), "/app/settings", &[]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "fallback for /app/settings");
+}
+
+#[test]
+fn fallback_does_not_replace_built_in_routes() {
+    let server = start_with_fallback();
+
+    let response = get(server.local_address(), "/echo/abc", &[]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "abc");
+}
+
+#[test]
+fn unknown_paths_are_not_found_without_a_fallback() {
+    let router = Router::new(ServerConfiguration::default());
+    let server = Server::start_with_handler("127.0.0.1:0", router).unwrap();
+
+    assert_eq!(get(server.local_address(), "/app/settings", &[]).status, 404);
+}
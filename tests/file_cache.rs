@@ -0,0 +1,93 @@
+mod common;
+
+use std::sync::Arc;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::file_cache::FileCache;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, start, TestDirectory };
+
+#[test]
+fn repeated_requests_are_counted_as_cache_hits() {
+    let directory = TestDirectory::new();
+    directory.write("page.txt", b"cached page");
+    let server = start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        file_cache: Some(Arc::new(FileCache::new(1024))),
+        ..ServerConfiguration::default()
+    });
+
+    assert_eq!(get(server.local_address(), "/files/page.txt", &[]).body, b"cached page");
+    assert_eq!(get(server.local_address(), "/files/page.txt", &[]).body, b"cached page");
+
+    assert_eq!(server.metrics().file_cache_misses(), 1);
+    assert_eq!(server.metrics().file_cache_hits(), 1);
+}
+
+#[test]
+fn metrics_report_no_cache_activity_without_a_cache() {
+    let directory = TestDirectory::new();
+    directory.write("page.txt", b"uncached page");
+    let server = common::start_serving(&directory);
+
+    assert_eq!(get(server.local_address(), "/files/page.txt", &[]).status, 200);
+
+    assert_eq!(server.metrics().file_cache_hits(), 0);
+    assert_eq!(server.metrics().file_cache_misses(), 0);
+}
+
+#[test]
+fn preload_skips_files_that_are_streamed() {
+    let directory = TestDirectory::new();
+    directory.write("small.txt", b"small");
+    directory.write("large.bin", &vec![0; 2 * 1024 * 1024]);
+    let file_cache = FileCache::new(8 * 1024 * 1024);
+
+    let preloaded = file_cache.preload(directory.path(), &[String::from("*")]);
+
+    assert_eq!(preloaded, 1);
+}
+
+fn start_with_preload(directory: &TestDirectory, patterns: &[&str]) -> Server {
+    start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        file_cache: Some(Arc::new(FileCache::new(1024 * 1024))),
+        preload: patterns.iter().copied().map(String::from).collect(),
+        ..ServerConfiguration::default()
+    })
+}
+
+#[test]
+fn first_request_after_preload_is_a_cache_hit() {
+    let directory = TestDirectory::new();
+    directory.write("assets/app.js", b"console.log('preloaded');");
+    directory.write("other.txt", b"not preloaded");
+    let server = start_with_preload(&directory, &["assets/*.js"]);
+
+    assert_eq!(get(server.local_address(), "/assets/app.js", &[]).body, b"console.log('preloaded');");
+    assert_eq!((server.metrics().file_cache_hits(), server.metrics().file_cache_misses()), (1, 0));
+
+    assert_eq!(get(server.local_address(), "/other.txt", &[]).body, b"not preloaded");
+    assert_eq!(server.metrics().file_cache_misses(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+#[ignore = "requires non-root"]
+fn unreadable_subdirectory_does_not_prevent_startup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let directory = TestDirectory::new();
+    directory.write("public/index.txt", b"readable");
+    directory.write("private/secret.txt", b"unreadable");
+    let private = directory.path().join("private");
+    std::fs::set_permissions(&private, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+    let server = start_with_preload(&directory, &["**"]);
+    let response = get(server.local_address(), "/public/index.txt", &[]);
+
+    std::fs::set_permissions(&private, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(server.metrics().file_cache_hits(), 1);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{ get, start_serving, TestDirectory };
+
+#[test]
+fn missing_file_is_not_found() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+
+    assert_eq!(get(server.local_address(), "/files/missing.txt", &[]).status, 404);
+}
+
+#[cfg(unix)]
+#[test]
+#[ignore = "requires non-root"]
+fn unreadable_file_is_forbidden() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let directory = TestDirectory::new();
+    let secret = directory.write("secret.txt", b"secret");
+    fs::set_permissions(&secret, fs::Permissions::from_mode(0o000)).unwrap();
+    let server = start_serving(&directory);
+
+    let response = get(server.local_address(), "/files/secret.txt", &[]);
+
+    assert_eq!(response.status, 403);
+    assert!(response.body.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn unexpected_file_system_error_is_an_internal_server_error() {
+    use std::os::unix::net::UnixListener;
+
+    let directory = TestDirectory::new();
+    // Exists, but reading a socket as a file fails with an error that is neither NotFound nor PermissionDenied
+    let _socket = UnixListener::bind(directory.path().join("socket")).unwrap();
+    let server = start_serving(&directory);
+
+    let response = get(server.local_address(), "/files/socket", &[]);
+
+    assert_eq!(response.status, 500);
+}
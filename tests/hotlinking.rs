@@ -0,0 +1,55 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, start, TestDirectory };
+
+fn start_protecting(directory: &TestDirectory, allow_missing_referer: bool) -> Server {
+    directory.write("logo.png", b"not really a png");
+    directory.write("notes.txt", b"notes");
+    start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        allowed_referers: vec![String::from("example.com")],
+        allow_missing_referer,
+        ..ServerConfiguration::default()
+    })
+}
+
+#[test]
+fn image_is_served_to_an_allowed_referer() {
+    let directory = TestDirectory::new();
+    let server = start_protecting(&directory, true);
+
+    let response = get(server.local_address(), "/logo.png", &[("Referer", "https://example.com:8080/page.html")]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"not really a png");
+}
+
+#[test]
+fn image_is_forbidden_to_another_referer() {
+    let directory = TestDirectory::new();
+    let server = start_protecting(&directory, true);
+
+    assert_eq!(get(server.local_address(), "/logo.png", &[("Referer", "https://elsewhere.org/page.html")]).status, 403);
+    assert_eq!(get(server.local_address(), "/files/logo.png", &[("Referer", "https://elsewhere.org/page.html")]).status, 403);
+}
+
+#[test]
+fn missing_referer_follows_the_configuration() {
+    let directory = TestDirectory::new();
+    let lenient = start_protecting(&directory, true);
+    let strict = start_protecting(&directory, false);
+
+    assert_eq!(get(lenient.local_address(), "/logo.png", &[]).status, 200);
+    assert_eq!(get(strict.local_address(), "/logo.png", &[]).status, 403);
+}
+
+#[test]
+fn other_content_is_not_protected() {
+    let directory = TestDirectory::new();
+    let server = start_protecting(&directory, false);
+
+    assert_eq!(get(server.local_address(), "/notes.txt", &[("Referer", "https://elsewhere.org/")]).status, 200);
+}
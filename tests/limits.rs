@@ -0,0 +1,52 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, request, start, TestDirectory };
+
+fn start_with_memory_budget(directory: &TestDirectory, max_request_memory: usize) -> Server {
+    start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        max_request_memory: Some(max_request_memory),
+        ..ServerConfiguration::default()
+    })
+}
+
+#[test]
+fn request_within_the_memory_budget_is_served() {
+    let directory = TestDirectory::new();
+    let server = start_with_memory_budget(&directory, 1024);
+
+    let response = request(server.local_address(), "POST", "/files/small.txt", &[], &[b'a'; 100]);
+
+    assert_eq!(response.status, 201);
+    assert_eq!(std::fs::read(directory.path().join("small.txt")).unwrap(), vec![b'a'; 100]);
+}
+
+#[test]
+fn headers_and_body_together_exceeding_the_budget_are_rejected_before_dispatch() {
+    let directory = TestDirectory::new();
+    let server = start_with_memory_budget(&directory, 1024);
+    // Neither the header block nor the body alone exceeds the budget, together they do
+    let padding = "x".repeat(600);
+    let body = vec![b'a'; 600];
+
+    let response = request(server.local_address(), "POST", "/files/large.txt", &[("X-Padding", &padding)], &body);
+
+    assert_eq!(response.status, 413);
+    assert!(!directory.path().join("large.txt").exists(), "the rejected upload was written");
+}
+
+#[test]
+fn small_request_with_a_large_buffered_response_is_rejected() {
+    let directory = TestDirectory::new();
+    directory.write("small.txt", &[b'a'; 100]);
+    directory.write("large.txt", &[b'a'; 4096]);
+    let server = start_with_memory_budget(&directory, 1024);
+
+    assert_eq!(get(server.local_address(), "/files/small.txt", &[]).status, 200);
+    let response = get(server.local_address(), "/files/large.txt", &[]);
+    assert_eq!(response.status, 413);
+    assert!(response.body.is_empty());
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::http::connection::MemoryConnection;
+use http_server_starter_rust::http::types::{ HttpRequest, HttpResponse };
+use http_server_starter_rust::router::Router;
+use http_server_starter_rust::server::serve;
+
+use common::Response;
+
+#[test]
+fn request_and_response_cycle_runs_without_a_socket() {
+    let server_configuration = ServerConfiguration::default();
+    let connection = MemoryConnection::new("GET /echo/in-memory HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    let connection = serve(connection, Router::new(server_configuration.clone()), server_configuration);
+
+    let response = Response::parse(connection.output()).unwrap();
+    assert_eq!(response.status_line, "HTTP/1.1 200 OK");
+    assert_eq!(response.header("Content-Type"), Some("text/plain"));
+    assert_eq!(response.body, b"in-memory");
+    assert!(response.rest.is_empty());
+}
+
+#[test]
+fn pipelined_requests_are_answered_in_order() {
+    let handler = |request: &HttpRequest| HttpResponse::builder(200).text(&request.uri).build();
+    let connection = MemoryConnection::new("GET /first HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\nConnection: close\r\n\r\nGET /ignored HTTP/1.1\r\n\r\n");
+
+    let connection = serve(connection, handler, ServerConfiguration::default());
+
+    let first = Response::parse(connection.output()).unwrap();
+    assert_eq!(first.body, b"/first");
+    let second = Response::parse(&first.rest).unwrap();
+    assert_eq!(second.body, b"/second");
+    assert_eq!(second.header("Connection"), Some("close"));
+    assert!(second.rest.is_empty());
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, start, TestDirectory };
+
+fn start_normalizing(directory: &TestDirectory) -> Server {
+    start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        normalize_paths: true,
+        ..ServerConfiguration::default()
+    })
+}
+
+#[test]
+fn repeated_slashes_and_dot_segments_reach_the_route() {
+    let directory = TestDirectory::new();
+    directory.write("docs/a.txt", b"contents");
+    let server = start_normalizing(&directory);
+
+    let response = get(server.local_address(), "//files/./docs//a.txt", &[]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "contents");
+}
+
+#[test]
+fn parent_segments_are_still_rejected() {
+    let directory = TestDirectory::new();
+    directory.write("docs/a.txt", b"contents");
+    let server = start_normalizing(&directory);
+
+    assert_eq!(get(server.local_address(), "/files/docs/../docs/a.txt", &[]).status, 404);
+    assert_eq!(get(server.local_address(), "/files/%2e%2e/a.txt", &[]).status, 404);
+}
+
+#[test]
+fn paths_are_left_alone_unless_enabled() {
+    let directory = TestDirectory::new();
+    directory.write("docs/a.txt", b"contents");
+    let server = start(ServerConfiguration {
+        directory: Some(directory.path_string()),
+        normalize_paths: false,
+        ..ServerConfiguration::default()
+    });
+
+    assert_eq!(get(server.local_address(), "//files/docs/a.txt", &[]).status, 404);
+}
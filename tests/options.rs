@@ -0,0 +1,78 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+
+use common::{ request, send, start, start_serving, TestDirectory };
+
+#[test]
+fn options_asterisk_lists_every_server_method() {
+    let server = start(ServerConfiguration::default());
+
+    let response = send(server.local_address(), "OPTIONS * HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+
+    assert_eq!(response.status, 204);
+    assert_eq!(response.header("Allow"), Some("GET, HEAD, POST, PUT, OPTIONS"));
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn options_on_a_route_lists_its_methods() {
+    let directory = TestDirectory::new();
+    directory.write("page.html", b"<p>page</p>");
+    let server = start_serving(&directory);
+
+    let files = request(server.local_address(), "OPTIONS", "/files/anything.txt", &[], b"");
+    assert_eq!(files.status, 204);
+    assert_eq!(files.header("Allow"), Some("GET, HEAD, POST, PUT, OPTIONS"));
+
+    let echo = request(server.local_address(), "OPTIONS", "/echo/hello", &[], b"");
+    assert_eq!(echo.status, 204);
+    assert_eq!(echo.header("Allow"), Some("GET, HEAD, OPTIONS"));
+
+    let page = request(server.local_address(), "OPTIONS", "/page.html", &[], b"");
+    assert_eq!(page.status, 204);
+    assert_eq!(page.header("Allow"), Some("GET, HEAD, OPTIONS"));
+}
+
+#[test]
+fn options_on_a_missing_static_file_is_not_found() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "OPTIONS", "/missing.html", &[], b"");
+
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Allow"), None);
+}
+
+#[test]
+fn methods_outside_allow_are_rejected_with_405() {
+    let directory = TestDirectory::new();
+    directory.write("x", b"keep me");
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "DELETE", "/files/x", &[], b"");
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("GET, HEAD, POST, PUT, OPTIONS"));
+    assert!(directory.path().join("x").exists());
+
+    let response = request(server.local_address(), "POST", "/echo/hello", &[], b"body");
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("GET, HEAD, OPTIONS"));
+}
+
+#[test]
+fn root_keeps_answering_every_implemented_method() {
+    let server = start(ServerConfiguration::default());
+
+    let options = request(server.local_address(), "OPTIONS", "/", &[], b"");
+    assert_eq!(options.status, 204);
+    assert_eq!(options.header("Allow"), Some("GET, HEAD, POST, PUT, OPTIONS"));
+
+    assert_eq!(request(server.local_address(), "POST", "/", &[], b"body").status, 200);
+    assert_eq!(request(server.local_address(), "PUT", "/", &[], b"body").status, 200);
+    // No route implements DELETE, so it is not part of the root's Allow header either
+    let delete = request(server.local_address(), "DELETE", "/", &[], b"");
+    assert_eq!(delete.status, 405);
+    assert_eq!(delete.header("Allow"), Some("GET, HEAD, POST, PUT, OPTIONS"));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{ get, start_serving, TestDirectory };
+
+#[test]
+fn nul_byte_in_the_path_is_rejected() {
+    let directory = TestDirectory::new();
+    directory.write("secret.txt", b"secret");
+    let server = start_serving(&directory);
+
+    let response = get(server.local_address(), "/files/secret%00.txt", &[]);
+
+    assert_eq!(response.status, 400);
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn control_character_in_a_static_path_is_rejected() {
+    let directory = TestDirectory::new();
+    directory.write("index.html", b"<h1>home</h1>");
+    let server = start_serving(&directory);
+
+    assert_eq!(get(server.local_address(), "/index%01.html", &[]).status, 400);
+}
+
+#[test]
+fn normal_path_is_served() {
+    let directory = TestDirectory::new();
+    directory.write("secret.txt", b"secret");
+    let server = start_serving(&directory);
+
+    let response = get(server.local_address(), "/files/secret.txt", &[]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "secret");
+}
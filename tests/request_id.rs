@@ -0,0 +1,44 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::http::types::{ HttpRequest, HttpResponse };
+use http_server_starter_rust::server::Server;
+
+use common::{ get, start };
+
+#[test]
+fn client_request_id_is_propagated() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/echo/traced", &[("X-Request-Id", "upstream-1234")]);
+
+    assert_eq!(response.header("X-Request-Id"), Some("upstream-1234"));
+}
+
+#[test]
+fn request_id_is_generated_when_missing_or_invalid() {
+    let server = start(ServerConfiguration::default());
+
+    let first = get(server.local_address(), "/echo/a", &[]);
+    let second = get(server.local_address(), "/echo/b", &[]);
+    let invalid = get(server.local_address(), "/echo/c", &[("X-Request-Id", &"x".repeat(200))]);
+
+    let first_id = first.header("X-Request-Id").expect("no request id");
+    let second_id = second.header("X-Request-Id").expect("no request id");
+    assert!(!first_id.is_empty());
+    assert_ne!(first_id, second_id);
+    assert_ne!(invalid.header("X-Request-Id"), Some("x".repeat(200).as_str()));
+    assert!(invalid.header("X-Request-Id").is_some());
+}
+
+#[test]
+fn handlers_see_the_generated_request_id() {
+    let handler = |request: &HttpRequest| {
+        HttpResponse::builder(200).text(request.headers.get("X-Request-Id").unwrap_or("")).build()
+    };
+    let server = Server::start_with_handler("127.0.0.1:0", handler).unwrap();
+
+    let response = get(server.local_address(), "/", &[]);
+
+    assert_eq!(response.header("X-Request-Id"), Some(response.text().as_str()));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::io::{ Read, Write };
+use std::net::TcpStream;
+use std::time::{ Duration, Instant };
+
+use http_server_starter_rust::config::ServerConfiguration;
+
+use common::{ connect, get, read_response, start, Response };
+
+#[test]
+fn shutdown_wakes_the_blocked_accept_loop_promptly() {
+    let server = start(ServerConfiguration::default());
+    let address = server.local_address();
+    assert_eq!(get(address, "/", &[]).status, 200);
+
+    let started_at = Instant::now();
+    server.shutdown().unwrap();
+    server.wait();
+
+    let latency = started_at.elapsed();
+    assert!(latency < Duration::from_millis(500), "shutdown took {:?}", latency);
+    assert!(TcpStream::connect(address).is_err(), "the listener is still accepting");
+}
+
+#[test]
+fn repeated_shutdown_is_harmless() {
+    let server = start(ServerConfiguration::default());
+
+    server.shutdown().unwrap();
+    server.shutdown().unwrap();
+    server.wait();
+}
+
+#[test]
+fn request_limit_stops_accepting_but_completes_in_flight_requests() {
+    let server = start(ServerConfiguration { max_total_requests: Some(2), ..ServerConfiguration::default() });
+    let address = server.local_address();
+    let mut in_flight = connect(address);
+    in_flight.write_all(b"GET /echo/first HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    assert_eq!(read_response(&mut in_flight).text(), "first");
+    // Started before the limit is reached, but the request is not complete yet
+    in_flight.write_all(b"GET /echo/in-flight HTTP/1.1\r\nHost: localhost\r\n").unwrap();
+
+    assert_eq!(get(address, "/echo/last", &[]).status, 200);
+
+    in_flight.write_all(b"\r\n").unwrap();
+    let mut received: Vec<u8> = Vec::new();
+    in_flight.read_to_end(&mut received).unwrap();
+    let response = Response::parse(&received).unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "in-flight");
+    assert_eq!(response.header("Connection"), Some("close"));
+
+    server.wait();
+    assert!(TcpStream::connect(address).is_err(), "the listener is still accepting");
+}
+
+#[test]
+fn shutdown_closes_idle_persistent_connections() {
+    let server = start(ServerConfiguration::default());
+    let mut idle = connect(server.local_address());
+    idle.write_all(b"GET /echo/kept-alive HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let response = read_response(&mut idle);
+    assert_eq!(response.text(), "kept-alive");
+    assert_eq!(response.header("Connection"), None);
+
+    let started_at = Instant::now();
+    server.shutdown().unwrap();
+    server.wait();
+
+    let latency = started_at.elapsed();
+    assert!(latency < Duration::from_millis(500), "waiting for the idle connection took {:?}", latency);
+    let mut received: Vec<u8> = Vec::new();
+    assert_eq!(idle.read_to_end(&mut received).unwrap(), 0);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use std::time::{ Duration, SystemTime };
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::http::date::parse_http_date;
+
+use common::{ get, send, start };
+
+#[test]
+fn responses_carry_a_current_date_and_the_server_name() {
+    let server = start(ServerConfiguration::default());
+
+    let response = get(server.local_address(), "/echo/abc", &[]);
+
+    let date = parse_http_date(response.header("Date").expect("no Date header")).expect("unparseable Date header");
+    let skew = SystemTime::now().duration_since(date).unwrap_or_else(|error| error.duration());
+    assert!(skew < Duration::from_secs(5), "Date is off by {:?}", skew);
+    assert!(response.header("Server").is_some_and(|server| !server.is_empty()));
+}
+
+#[test]
+fn rejected_requests_carry_them_too() {
+    let server = start(ServerConfiguration::default());
+
+    let response = send(server.local_address(), &format!("GET / HTTP/1.1\r\nHost: localhost\r\nX-Padding: {}\r\n\r\n", "x".repeat(10000)));
+
+    assert_eq!(response.status, 431);
+    assert!(response.header("Date").is_some_and(|date| parse_http_date(date).is_some()));
+    assert!(response.header("Server").is_some());
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use http_server_starter_rust::config::ServerConfiguration;
+use http_server_starter_rust::server::Server;
+
+use common::{ get, start, start_serving, TestDirectory };
+
+fn start_with_index(directory: &TestDirectory, index: bool, autoindex: bool) -> Server {
+    start(ServerConfiguration { directory: Some(directory.path_string()), index, autoindex, ..ServerConfiguration::default() })
+}
+
+fn site() -> TestDirectory {
+    let directory = TestDirectory::new();
+    directory.write("docs/index.html", b"<h1>docs</h1>");
+    directory.write("docs/guide.txt", b"guide");
+    directory.write("docs/api/reference.txt", b"reference");
+    directory
+}
+
+#[test]
+fn index_file_is_served_when_enabled() {
+    let directory = site();
+    let server = start_with_index(&directory, true, true);
+
+    let response = get(server.local_address(), "/docs/", &[]);
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("text/html"));
+    assert_eq!(response.body, b"<h1>docs</h1>");
+}
+
+#[test]
+fn listing_is_generated_when_only_autoindex_is_enabled() {
+    let directory = site();
+    let server = start_with_index(&directory, false, true);
+
+    let response = get(server.local_address(), "/docs", &[]);
+
+    assert_eq!(response.status, 200);
+    let listing = response.text();
+    assert!(listing.contains("<title>Index of /docs/</title>"), "{}", listing);
+    assert!(listing.contains("<a href=\"/docs/api/\">api/</a>"), "{}", listing);
+    assert!(listing.contains("<a href=\"/docs/guide.txt\">guide.txt</a>"), "{}", listing);
+    assert!(listing.contains("<a href=\"/docs/index.html\">index.html</a>"), "{}", listing);
+}
+
+#[test]
+fn directory_is_not_found_when_both_are_disabled() {
+    let directory = site();
+    let server = start_with_index(&directory, false, false);
+
+    assert_eq!(get(server.local_address(), "/docs/", &[]).status, 404);
+    assert_eq!(get(server.local_address(), "/docs/guide.txt", &[]).status, 200);
+}
+
+#[test]
+fn listing_is_the_fallback_for_directories_without_an_index_file() {
+    let directory = site();
+    let server = start_with_index(&directory, true, true);
+
+    let response = get(server.local_address(), "/docs/api/", &[]);
+
+    assert_eq!(response.status, 200);
+    assert!(response.text().contains("reference.txt"));
+}
+
+#[test]
+fn root_serves_the_index_file() {
+    let directory = TestDirectory::new();
+    directory.write("index.html", b"<h1>home</h1>");
+    directory.write("style.css", b"body {}");
+    let server = start_serving(&directory);
+
+    let root = get(server.local_address(), "/", &[]);
+    assert_eq!(root.status, 200);
+    assert_eq!(root.header("Content-Type"), Some("text/html"));
+    assert_eq!(root.body, b"<h1>home</h1>");
+
+    let stylesheet = get(server.local_address(), "/style.css", &[]);
+    assert_eq!(stylesheet.header("Content-Type"), Some("text/css"));
+    assert_eq!(stylesheet.body, b"body {}");
+}
+
+#[test]
+fn missing_static_path_is_not_found() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+
+    assert_eq!(get(server.local_address(), "/missing.html", &[]).status, 404);
+    // Without an index file the root keeps answering with an empty 200
+    let root = get(server.local_address(), "/", &[]);
+    assert_eq!(root.status, 200);
+    assert!(root.body.is_empty());
+}
+
+#[test]
+fn listing_links_stay_on_the_server_for_repeated_slashes() {
+    let directory = site();
+    let server = start_with_index(&directory, false, true);
+
+    let listing = get(server.local_address(), "//docs/", &[]).text();
+
+    assert!(listing.contains("<a href=\"/docs/guide.txt\">guide.txt</a>"), "{}", listing);
+    assert!(!listing.contains("href=\"//"), "{}", listing);
+}
+
+#[test]
+fn listing_links_percent_encode_file_names() {
+    let directory = TestDirectory::new();
+    directory.write("my docs/50% off #1?.txt", b"sale");
+    let server = start_with_index(&directory, false, true);
+
+    let listing = get(server.local_address(), "/my%20docs/", &[]).text();
+
+    assert!(listing.contains("<title>Index of /my docs/</title>"), "{}", listing);
+    assert!(listing.contains("<a href=\"/my%20docs/50%25%20off%20%231%3F.txt\">50% off #1?.txt</a>"), "{}", listing);
+    let response = get(server.local_address(), "/my%20docs/50%25%20off%20%231%3F.txt", &[]);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"sale");
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::fs;
+
+use common::{ request, start_serving, TestDirectory };
+
+#[cfg(unix)]
+#[test]
+#[ignore = "requires non-root"]
+fn uploads_into_a_read_only_directory_are_forbidden() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let directory = TestDirectory::new();
+    let read_only = directory.path().join("read-only");
+    fs::create_dir(&read_only).unwrap();
+    fs::set_permissions(&read_only, fs::Permissions::from_mode(0o555)).unwrap();
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "POST", "/files/read-only/upload.txt", &[], b"content");
+
+    fs::set_permissions(&read_only, fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(response.status, 403);
+    assert!(!read_only.join("upload.txt").exists());
+}
+
+#[test]
+fn put_creates_a_missing_file() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "PUT", "/files/new.txt", &[], b"created");
+
+    assert_eq!(response.status, 201);
+    assert_eq!(fs::read(directory.path().join("new.txt")).unwrap(), b"created");
+}
+
+#[test]
+fn put_replaces_an_existing_file() {
+    let directory = TestDirectory::new();
+    directory.write("existing.txt", b"the original, longer content");
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "PUT", "/files/existing.txt", &[], b"replaced");
+
+    assert_eq!(response.status, 204);
+    assert!(response.body.is_empty());
+    assert_eq!(fs::read(directory.path().join("existing.txt")).unwrap(), b"replaced");
+}
+
+#[test]
+fn put_on_a_directory_is_a_conflict() {
+    let directory = TestDirectory::new();
+    directory.write("folder/inside.txt", b"inside");
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "PUT", "/files/folder", &[], b"not a directory");
+
+    assert_eq!(response.status, 409);
+    assert_eq!(response.status_line, "HTTP/1.1 409 Conflict");
+    assert!(directory.path().join("folder").is_dir());
+}
+
+#[test]
+fn post_creates_missing_parent_directories() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+
+    let response = request(server.local_address(), "POST", "/files/a/b/c/nested.txt", &[], b"deep");
+
+    assert_eq!(response.status, 201);
+    assert_eq!(fs::read(directory.path().join("a/b/c/nested.txt")).unwrap(), b"deep");
+}
+
+#[test]
+fn post_truncates_a_longer_existing_file() {
+    let directory = TestDirectory::new();
+    let server = start_serving(&directory);
+    let address = server.local_address();
+
+    assert_eq!(request(address, "POST", "/files/overwrite.txt", &[], b"a long original content").status, 201);
+    assert_eq!(request(address, "POST", "/files/overwrite.txt", &[], b"short").status, 201);
+
+    assert_eq!(fs::read(directory.path().join("overwrite.txt")).unwrap(), b"short");
+    let response = request(address, "GET", "/files/overwrite.txt", &[], b"");
+    assert_eq!(response.body, b"short");
+}